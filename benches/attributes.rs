@@ -0,0 +1,125 @@
+use std::collections::{HashMap, HashSet};
+
+use bevy_sprite_animation::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+enum Mood {
+    Idle,
+    Angry(u8),
+}
+
+/// how `AnimationState` stored attributes before they were typed, as the baseline:
+/// bincode bytes, the changed set and the type of every attribute set
+#[derive(Default)]
+struct BincodeState {
+    data: HashMap<Attribute, Vec<u8>>,
+    changed: HashSet<Attribute>,
+    data_type: HashMap<Attribute, &'static str>,
+}
+
+impl BincodeState {
+    // the old `get_attribute` built its panic message before checking the value
+    #[allow(clippy::expect_fun_call)]
+    fn get_attribute<D: DeserializeOwned>(&self, key: &Attribute) -> D {
+        self.data.get(key).and_then(|bytes| bincode::deserialize(bytes).ok()).expect(&format!("Attribute {} Exists", key))
+    }
+
+    fn set_attribute<D: Serialize>(&mut self, key: Attribute, value: D) {
+        let bytes = bincode::serialize(&value).expect("value to serialize");
+        self.changed.insert(key);
+        self.data.insert(key, bytes);
+        self.data_type.insert(key, std::any::type_name::<D>());
+    }
+}
+
+/// the calls a frame makes on one state, shared by both stores so they do the same work
+macro_rules! frame {
+    ($state:expr, $walk:expr) => {{
+        let state = $state;
+        state.set_attribute(Attribute::DELTA, 0.016f32);
+        let time = state.get_attribute::<f32>(&Attribute::TIME_ON_FRAME) + state.get_attribute::<f32>(&Attribute::DELTA);
+        state.set_attribute(Attribute::TIME_ON_FRAME, time % 0.1);
+        let frames = state.get_attribute::<usize>(&Attribute::FRAMES);
+        state.set_attribute(Attribute::FRAMES, (frames + 1) % 8);
+        let index = state.get_attribute::<usize>(&$walk);
+        state.set_attribute($walk, (index + 1) % 8);
+        black_box(state.get_attribute::<bool>(&Attribute::FLIP_X));
+    }};
+}
+
+/// the next value of a custom attribute flipped each frame
+fn next_mood(mood: Mood) -> Mood {
+    match mood {
+        Mood::Idle => Mood::Angry(3),
+        Mood::Angry(_) => Mood::Idle,
+    }
+}
+
+/// one state per entity with the attributes a frame reads
+fn states() -> Vec<AnimationState> {
+    (0..1000usize).map(|i| {
+        let mut state = AnimationState::default();
+        state.set_attribute(Attribute::TIME_ON_FRAME, 0.0f32);
+        state.set_attribute(Attribute::FRAMES, 0usize);
+        state.set_attribute(Attribute::FLIP_X, false);
+        state.set_attribute(Attribute::new_index("Walk"), i % 8);
+        state.set_attribute(Attribute::new_attribute("Mood"), if i % 2 == 0 {Mood::Idle} else {Mood::Angry(3)});
+        state
+    }).collect()
+}
+
+/// `states` in the bincode store
+fn bincode_states() -> Vec<BincodeState> {
+    (0..1000usize).map(|i| {
+        let mut state = BincodeState::default();
+        state.set_attribute(Attribute::TIME_ON_FRAME, 0.0f32);
+        state.set_attribute(Attribute::FRAMES, 0usize);
+        state.set_attribute(Attribute::FLIP_X, false);
+        state.set_attribute(Attribute::new_index("Walk"), i % 8);
+        state.set_attribute(Attribute::new_attribute("Mood"), if i % 2 == 0 {Mood::Idle} else {Mood::Angry(3)});
+        state
+    }).collect()
+}
+
+fn attributes(c: &mut Criterion) {
+    let walk = Attribute::new_index("Walk");
+    let mood = Attribute::new_attribute("Mood");
+
+    let mut group = c.benchmark_group("typed_store_1000_states");
+    let mut core = states();
+    // what an `FPSNode` and an `IndexNode` do each frame
+    group.bench_function("core", |b| b.iter(|| {
+        for state in core.iter_mut() {
+            frame!(state, walk);
+        }
+    }));
+    let mut custom = states();
+    group.bench_function("custom", |b| b.iter(|| {
+        for state in custom.iter_mut() {
+            let next = next_mood(state.get_attribute::<Mood>(&mood));
+            state.set_attribute(mood, next);
+        }
+    }));
+    group.finish();
+
+    let mut group = c.benchmark_group("bincode_map_1000_states");
+    let mut core = bincode_states();
+    group.bench_function("core", |b| b.iter(|| {
+        for state in core.iter_mut() {
+            frame!(state, walk);
+        }
+    }));
+    let mut custom = bincode_states();
+    group.bench_function("custom", |b| b.iter(|| {
+        for state in custom.iter_mut() {
+            let next = next_mood(state.get_attribute::<Mood>(&mood));
+            state.set_attribute(mood, next);
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, attributes);
+criterion_main!(benches);
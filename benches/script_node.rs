@@ -0,0 +1,45 @@
+use bevy_sprite_animation::prelude::*;
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+const SCRIPT: &str = "
+    #fallback NodeID(0x2)
+    set Index(Fall) = 6 - Index(Stand) * 2
+    if Index(Stand) >= 6 and not Index(Fall) == 1 {
+        set Index(Stand) = 0
+        return NodeID(0x1)
+    } else if Index(Stand) == 0 or Index(Fall) > 4 {
+        return NodeID(0x3)
+    }
+    set Index(Stand) = Index(Stand) + 1
+";
+
+/// one state per zombie, each at a different point of the script
+fn states() -> Vec<AnimationState> {
+    (0..500usize).map(|i| {
+        let mut state = AnimationState::default();
+        state.set_attribute(Attribute::new_index("Stand"), i % 7);
+        state
+    }).collect()
+}
+
+fn script_node(c: &mut Criterion) {
+    let node = ScriptNode::new(SCRIPT);
+    let mut group = c.benchmark_group("script_node_500_states");
+    let mut vm = states();
+    group.bench_function("bytecode", |b| b.iter(|| {
+        for state in vm.iter_mut() {
+            black_box(node.run(state));
+        }
+    }));
+    // the `Vec<Token>` walker scripts ran on before was replaced by the AST, so the AST interpreter is the baseline
+    let mut ast = states();
+    group.bench_function("ast_interpreter", |b| b.iter(|| {
+        for state in ast.iter_mut() {
+            black_box(node.interpret(state));
+        }
+    }));
+    group.finish();
+}
+
+criterion_group!(benches, script_node);
+criterion_main!(benches);
@@ -46,7 +46,7 @@ mod animation {
     fn zombie_state_update(
         mut zombies: Query<(&mut AnimationState, &ZState), (With<super::Zombie>, Changed<ZState>)>
     ) {
-        let att = Attribute::from_str("ZombieState");
+        let att: Attribute = "ZombieState".parse().unwrap();
         for (mut state, name) in zombies.iter_mut() {
             state.set_attribute(att, *name);
         }
@@ -56,7 +56,7 @@ mod animation {
     fn zombie_update_state(
         mut zombies: Query<(&AnimationState, &mut ZState), With<super::Zombie>>
     ) {
-        let attribute = Attribute::from_str("ZombieState");
+        let attribute: Attribute = "ZombieState".parse().unwrap();
         for (state,mut name) in zombies.iter_mut() {
             if state.changed(&attribute) {
                 *name = state.get_attribute::<ZState>(&attribute);
@@ -68,9 +68,13 @@ mod animation {
 
 mod player {
     use bevy::prelude::*;
-    use bevy_sprite_animation::{state::AnimationState, prelude::Attribute};
+    use bevy_sprite_animation::{state::AnimationState, prelude::TypedAttribute};
 
     use super::animation::ZState;
+
+    const ZOMBIE_STATE: TypedAttribute<ZState> = TypedAttribute::new("ZombieState");
+    const STAND: TypedAttribute<usize> = TypedAttribute::index("Stand");
+    const FALL: TypedAttribute<usize> = TypedAttribute::index("Fall");
     
     #[derive(Debug, Component)]
     pub struct Player;
@@ -83,25 +87,19 @@ mod player {
     
     fn player_animation_update(
         mut player: Query<(&mut ZState, &mut AnimationState), With<Player>>,
-        mut local: Local<[Attribute; 3]>,
         input: Res<Input<KeyCode>>,
     ){
-        if local[0] == Attribute::NULL {
-            local[0] = Attribute::from_str("ZombieState");
-            local[1] = Attribute::new_index("Stand");
-            local[2] = Attribute::new_index("Fall");
-        }
         let (mut zstate, mut animation) = player.single_mut();
         for key in input.get_just_pressed() {
             match key {
                 KeyCode::A | KeyCode::Left => {
                     if zstate.as_ref() == &ZState::Walking || zstate.as_ref() == &ZState::Idle {
-                        animation.set_attribute(Attribute::FLIP_X, true);
+                        animation.set(TypedAttribute::FLIP_X, true);
                     }
                 },
                 KeyCode::D | KeyCode::Right => {
                     if zstate.as_ref() == &ZState::Walking || zstate.as_ref() == &ZState::Idle {
-                        animation.set_attribute(Attribute::FLIP_X, false);
+                        animation.set(TypedAttribute::FLIP_X, false);
                     }
                 },
                 KeyCode::ShiftLeft | KeyCode::ShiftRight | KeyCode::Up => {
@@ -126,11 +124,11 @@ mod player {
                         ZState::Running => {ZState::Walking},
                         ZState::Attacking => {ZState::Attacking},
                         ZState::FallF => {ZState::FallF},
-                        ZState::StandF => {let index = animation.get_attribute::<usize>(&local[1]);
-                            animation.set_attribute(local[2], 6 - index); ZState::FallF},
+                        ZState::StandF => {let index = animation.get(STAND);
+                            animation.set(FALL, 6 - index); ZState::FallF},
                         ZState::FallB => {ZState::FallB},
-                        ZState::StandB => {let index = animation.get_attribute::<usize>(&local[1]);
-                            animation.set_attribute(local[2], 7 - index); ZState::FallB},
+                        ZState::StandB => {let index = animation.get(STAND);
+                            animation.set(FALL, 7 - index); ZState::FallB},
                         ZState::Test => {ZState::Test},
                         ZState::LayingB => {ZState::LayingB},
                         ZState::LayingF => {ZState::LayingF},
@@ -145,7 +143,7 @@ mod player {
                 _ => {}
             }
         }
-        animation.set_attribute(local[0], *zstate);
+        animation.set(ZOMBIE_STATE, *zstate);
     }
 }
 
@@ -155,7 +153,8 @@ fn main() {
         default_sampler: ImageSampler::nearest_descriptor(),
     }))
     .add_plugins((animation::YourAnimationPlugin,
-        SpriteAnimationPlugin::<Zombie>::default(),
+        // lets scripts set ZombieState before it has been set in rust
+        SpriteAnimationPlugin::<Zombie>::default().with_attribute::<ZState>("ZombieState".parse().unwrap()),
         player::Player))
     .add_systems(Startup ,setup_animations)
     .run()
@@ -183,10 +182,6 @@ fn setup_animations(
         bevy_sprite_animation::nodes::IndexNode::new("test", &images, true)
     ));
 
-    let fall_index = Attribute::new_index("Fall");
-    let stand_index = Attribute::new_index("Stand");
-    let attack_index = Attribute::new_index("Attack");
-
     if let Err(e) = nodes.load("test.node", &asset_server) {
         error!("{}", e)
     }
@@ -195,10 +190,7 @@ fn setup_animations(
         error!("{}", e)
     }
 
-    let mut start = AnimationState::default();
-    start.set_temporary(fall_index);
-    start.set_temporary(stand_index);
-    start.set_temporary(attack_index);
+    let start = AnimationState::default();
     commands.spawn((SpriteBundle{
         transform: Transform::from_translation(Vec3::X * 10.),
         sprite: Sprite{custom_size: Some(Vec2::splat(1000.)), ..Default::default()},
@@ -208,6 +200,6 @@ fn setup_animations(
     ZState::Attacking,
     start,
     player::Player,
-    StartNode::from_str("0x0")
+    StartNode::from_u64(0x0)
     ));
 }
\ No newline at end of file
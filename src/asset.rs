@@ -0,0 +1,216 @@
+use bevy::asset::{AssetLoader, AssetPath, BoxedFuture, LoadContext, LoadedAsset};
+use bevy::prelude::*;
+use bevy::reflect::{TypePath, TypeUuid};
+
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use crate::test::test_asset_server;
+    use super::*;
+
+    #[test]
+    fn frame_paths_index_and_variable() {
+        let paths = frame_paths("attributes: { Index(frame_paths): 3 temp }, [
+            FPSNode(name: \"frame_paths\", fps: 7, then: NodeID(\"0x1\"),),
+            IndexNode(
+            name: \"Zombie1_Idle\",
+            frames: [
+            Zombie1/Zombie1_00000.png,
+            Zombie1/Zombie1_00001.png,
+            ],
+            ),
+            VariableNode(
+            name: \"Zombie1_Walk\",
+            frames: [
+            (Zombie1/Zombie1_00002.png, 0.1),
+            (Zombie1/Zombie1_00003.png, 0.2),
+            ],
+            ),
+            IndexNode(
+            name: \"Zombie1_Sheet\",
+            sheet: (path: \"Zombie1/Zombie1_sheet.png\", layout: Grid(tile_size: (64., 64.), columns: 8, rows: 9)),
+            frames: [0, 1, 2],
+            ),
+        ]").unwrap();
+        let expected: Vec<AssetPath> = vec![
+            "Zombie1/Zombie1_00000.png".into(),
+            "Zombie1/Zombie1_00001.png".into(),
+            "Zombie1/Zombie1_00002.png".into(),
+            "Zombie1/Zombie1_00003.png".into(),
+            "Zombie1/Zombie1_sheet.png".into(),
+        ];
+        assert_eq!(paths, expected);
+    }
+
+    #[test]
+    fn reload_replaces_nodes() {
+        let asset_server = test_asset_server();
+        let mut tree = AnimationNodeTree::<()>::default();
+        let handle = Handle::<NodeTreeAsset>::weak(bevy::asset::HandleId::random::<NodeTreeAsset>());
+        let first = NodeTreeAsset {
+            data: "[FPSNode(name: \"reload_first\", fps: 7, then: NodeID(\"0x1\"),),]".to_string(),
+            is_tree: true,
+        };
+        let ids = tree.load_asset(&handle, &first, &asset_server).unwrap();
+        assert_eq!(ids, vec![NodeID::from_name("reload_first")]);
+
+        let second = NodeTreeAsset {
+            data: "FPSNode(name: \"reload_second\", fps: 7, then: NodeID(\"0x1\"),)".to_string(),
+            is_tree: false,
+        };
+        tree.load_asset(&handle, &second, &asset_server).unwrap();
+        assert!(tree.get_node(NodeID::from_name("reload_first")).is_none());
+        assert!(tree.get_node(NodeID::from_name("reload_second")).is_some());
+    }
+
+    #[test]
+    fn failed_reload_keeps_nodes() {
+        let asset_server = test_asset_server();
+        let mut tree = AnimationNodeTree::<()>::default();
+        let handle = Handle::<NodeTreeAsset>::weak(bevy::asset::HandleId::random::<NodeTreeAsset>());
+        let first = NodeTreeAsset {
+            data: "[FPSNode(name: \"keep_first\", fps: 7, then: NodeID(\"0x1\"),),]".to_string(),
+            is_tree: true,
+        };
+        let ids = tree.load_asset(&handle, &first, &asset_server).unwrap();
+        tree.names_mut().insert_node(NodeID::from_name("keep_taken"), "keep_other").unwrap();
+        tree.names_mut().set_strict(true);
+        let second = NodeTreeAsset {
+            data: "[FPSNode(name: \"keep_second\", fps: 7, then: NodeID(\"0x1\"),), FPSNode(name: \"keep_taken\", fps: 7, then: NodeID(\"0x1\"),),]".to_string(),
+            is_tree: true,
+        };
+        assert!(matches!(tree.load_asset(&handle, &second, &asset_server), Err(BevySpriteAnimationError::NameCollision { .. })));
+        assert!(tree.get_node(ids[0]).is_some());
+        assert!(tree.get_node(NodeID::from_name("keep_second")).is_none());
+        assert_eq!(tree.sources[&handle], Some(ids));
+    }
+
+    #[test]
+    fn reload_keeps_nodes_of_other_assets() {
+        let asset_server = test_asset_server();
+        let mut tree = AnimationNodeTree::<()>::default();
+        let (a, b) = (bevy::asset::HandleId::random::<NodeTreeAsset>(), bevy::asset::HandleId::random::<NodeTreeAsset>());
+        let (a, b) = (Handle::<NodeTreeAsset>::weak(a), Handle::<NodeTreeAsset>::weak(b));
+        let shared = NodeTreeAsset {
+            data: "FPSNode(name: \"shared_node\", fps: 7, then: NodeID(\"0x1\"),)".to_string(),
+            is_tree: false,
+        };
+        let id = tree.load_asset(&a, &shared, &asset_server).unwrap()[0];
+        tree.load_asset(&b, &shared, &asset_server).unwrap();
+        let other = NodeTreeAsset {
+            data: "FPSNode(name: \"shared_other\", fps: 7, then: NodeID(\"0x1\"),)".to_string(),
+            is_tree: false,
+        };
+        tree.load_asset(&a, &other, &asset_server).unwrap();
+        assert!(tree.get_node(id).is_some());
+        assert!(tree.get_node(NodeID::from_name("shared_other")).is_some());
+    }
+}
+
+/// The raw contents of a `.node` or `.nodetree` file
+/// nodes are built from it by each `AnimationNodeTree<F>` that loaded it
+/// so that loaders registered on that tree are used
+#[derive(Debug, TypeUuid, TypePath)]
+#[uuid = "234559a3-807e-4f2f-9369-3d7ff1c76bfa"]
+pub struct NodeTreeAsset {
+    pub(crate) data: String,
+    pub(crate) is_tree: bool,
+}
+
+impl NodeTreeAsset {
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// true if this asset was loaded from a `.nodetree` file
+    pub fn is_tree(&self) -> bool {
+        self.is_tree
+    }
+}
+
+#[derive(Default)]
+pub struct NodeTreeAssetLoader;
+
+impl AssetLoader for NodeTreeAssetLoader {
+    fn load<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<(), bevy::asset::Error>> {
+        Box::pin(async move {
+            let data = String::from_utf8(bytes.to_vec())?;
+            let is_tree = load_context.path().extension().is_some_and(|ext| ext == "nodetree");
+            let dependencies = frame_paths(&data)?;
+            load_context.set_default_asset(LoadedAsset::new(NodeTreeAsset { data, is_tree }).with_dependencies(dependencies));
+            Ok(())
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["node", "nodetree"]
+    }
+}
+
+/// finds the sheet or frame paths of every `IndexNode` and `VariableNode` in `data`
+/// splits the nodes and their fields the same way loading them does; atlas indices and other node types are skipped
+fn frame_paths(data: &str) -> Result<Vec<AssetPath<'static>>, BevySpriteAnimationError> {
+    let data = match crate::defaults::parse_attributes(data)? {
+        Some((_, rest)) => rest,
+        None => data,
+    };
+    let mut paths = Vec::new();
+    for node in crate::split_nodes(data)? {
+        let (_, kind, node) = crate::node_parts(node)?;
+        if kind != "IndexNode" && kind != "VariableNode" {
+            continue;
+        }
+        let fields = crate::nodes::node_fields(node);
+        if let Some(sheet) = fields.get("sheet") {
+            let sheet: SpriteSheet = ron::from_str(sheet)?;
+            paths.push(AssetPath::from(sheet.path).to_owned());
+            continue;
+        }
+        let Some(frames) = fields.get("frames") else {continue;};
+        if kind == "IndexNode" {
+            paths.extend(crate::nodes::index_frames(frames).map(|path| AssetPath::from(path).to_owned()));
+        } else {
+            for (path, _) in crate::nodes::variable_frames(frames)? {
+                paths.push(AssetPath::from(path).to_owned());
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// replaces the nodes of every `NodeTreeAsset` the tree loaded when it is created or changed on disk
+pub(crate) fn reload_node_trees<F: Component>(
+    mut tree: ResMut<AnimationNodeTree<F>>,
+    mut events: EventReader<AssetEvent<NodeTreeAsset>>,
+    assets: Res<Assets<NodeTreeAsset>>,
+    asset_server: Res<AssetServer>,
+    starts: Query<&StartNode, With<F>>,
+) {
+    let mut loaded = false;
+    for event in events.iter() {
+        let handle = match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => handle,
+            AssetEvent::Removed { .. } => continue,
+        };
+        if !tree.has_source(handle) {
+            continue;
+        }
+        let Some(asset) = assets.get(handle) else {continue;};
+        let path = asset_server.get_handle_path(handle);
+        match tree.load_asset(handle, asset, &asset_server) {
+            Ok(ids) => {info!("loaded {:?} from {:?}", ids, path); loaded = true;},
+            Err(e) => error!("failed to load {:?}: {}", path, tree.display(&e)),
+        }
+    }
+    if loaded && tree.validate_on_load && !tree.is_loading() {
+        let report = tree.validate(starts.iter().map(|start| start.0));
+        if !report.is_ok() {
+            warn!("{}", tree.display(&report));
+        }
+    }
+}
@@ -1,8 +1,11 @@
-use std::collections::HashMap;
+use std::marker::PhantomData;
 
 use bevy::reflect::Reflect;
 use bevy::reflect::ReflectDeserialize;
 use bevy::reflect::ReflectSerialize;
+use crate::error::BevySpriteAnimationError;
+use crate::hash::StableHasher;
+use crate::names::NameRegistry;
 
 #[cfg(test)]
 mod test{
@@ -12,24 +15,62 @@ mod test{
     fn custom_from_str() {
         let test = Attribute::new_attribute("Test");
         assert_eq!(test, Attribute::new_attribute("Test"));
-        assert_eq!(test, Attribute::from_str("Test"));
-        assert_eq!(test, Attribute::from_str("Attribute(Test)"));
+        assert_eq!(test, "Test".parse::<Attribute>().unwrap());
+        assert_eq!(test, "Attribute(Test)".parse::<Attribute>().unwrap());
     }
 
     #[test]
     fn statics_work() {
-        assert_eq!(Attribute::DELTA, Attribute::from_str("Delta"));
-        assert_eq!(Attribute::DELTA, Attribute::from_str("Core(Delta)"));
+        assert_eq!(Attribute::DELTA, "Delta".parse::<Attribute>().unwrap());
+        assert_eq!(Attribute::DELTA, "Core(Delta)".parse::<Attribute>().unwrap());
         assert_ne!(Attribute::DELTA, Attribute::new_attribute("Delta"));
-        assert_ne!(Attribute::DELTA, Attribute::from_str("Attribute(Delta)"));
-        assert_eq!(Attribute::FRAMES, Attribute::from_str("Frames"));
-        assert_eq!(Attribute::FRAMES, Attribute::from_str("Core(Frames)"));
+        assert_ne!(Attribute::DELTA, "Attribute(Delta)".parse::<Attribute>().unwrap());
+        assert_eq!(Attribute::FRAMES, "Frames".parse::<Attribute>().unwrap());
+        assert_eq!(Attribute::FRAMES, "Core(Frames)".parse::<Attribute>().unwrap());
         assert_ne!(Attribute::FRAMES, Attribute::new_attribute("Frames"));
-        assert_ne!(Attribute::FRAMES, Attribute::from_str("Attribute(Frames)"));
-        assert_eq!(Attribute::TIME_ON_FRAME, Attribute::from_str("FrameTime"));
-        assert_eq!(Attribute::TIME_ON_FRAME, Attribute::from_str("Core(FrameTime)"));
+        assert_ne!(Attribute::FRAMES, "Attribute(Frames)".parse::<Attribute>().unwrap());
+        assert_eq!(Attribute::TIME_ON_FRAME, "FrameTime".parse::<Attribute>().unwrap());
+        assert_eq!(Attribute::TIME_ON_FRAME, "Core(FrameTime)".parse::<Attribute>().unwrap());
         assert_ne!(Attribute::TIME_ON_FRAME, Attribute::new_attribute("FrameTime"));
-        assert_ne!(Attribute::TIME_ON_FRAME, Attribute::from_str("Attribute(FrameTime)"));
+        assert_ne!(Attribute::TIME_ON_FRAME, "Attribute(FrameTime)".parse::<Attribute>().unwrap());
+    }
+
+    #[test]
+    fn typed_keys_match() {
+        const WALK: TypedAttribute<usize> = TypedAttribute::index("typed_walk");
+        const MOOD: TypedAttribute<String> = TypedAttribute::new("typed_mood");
+        assert_eq!(WALK.attribute(), Attribute::new_index("typed_walk"));
+        assert_eq!(MOOD.attribute(), Attribute::new_attribute("typed_mood"));
+        assert_eq!(TypedAttribute::FRAMES.attribute(), Attribute::FRAMES);
+        let mut names = NameRegistry::default();
+        let named = TypedAttribute::<usize>::index("typed_named").register(&mut names).unwrap();
+        assert_eq!(names.attribute_name(named), Some("typed_named"));
+        assert_eq!(names.attribute_name(WALK.attribute()), None);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("Index()".parse::<Attribute>().is_err());
+        assert!("Index(0xZZ)".parse::<Attribute>().is_err());
+        assert!("Index(0x5)".parse::<Attribute>().is_err());
+        assert!("Attribute(0x1234)".parse::<Attribute>().is_err());
+        assert!("Core(Speed)".parse::<Attribute>().is_err());
+        assert!("Other(Speed)".parse::<Attribute>().is_err());
+        assert!("Index(Speed".parse::<Attribute>().is_err());
+        assert!(matches!("".parse::<Attribute>(), Err(BevySpriteAnimationError::ParseError { .. })));
+    }
+
+    #[test]
+    fn core_names() {
+        assert_eq!("Core(FlipX)".parse::<Attribute>().unwrap(), Attribute::FLIP_X);
+        assert_eq!("FLIP_Y".parse::<Attribute>().unwrap(), Attribute::FLIP_Y);
+        assert_eq!("Core(LAST_FPS)".parse::<Attribute>().unwrap(), Attribute::LAST_FPS);
+        assert_eq!("LastFPS".parse::<Attribute>().unwrap(), Attribute::LAST_FPS);
+        for att in [Attribute::DELTA, Attribute::FRAMES, Attribute::TIME_ON_FRAME, Attribute::FLIP_X, Attribute::FLIP_Y, Attribute::LAST_FPS] {
+            assert_eq!(att.to_string().parse::<Attribute>().unwrap(), att);
+        }
+        let unnamed = "Attribute(0x00000000DEADBEEF)".parse::<Attribute>().unwrap();
+        assert_eq!(unnamed.to_string().parse::<Attribute>().unwrap(), unnamed);
     }
 
     #[test]
@@ -37,8 +78,8 @@ mod test{
         let test = Attribute::new_index("Test");
         assert!(test.0 < 65536);
         assert_eq!(test, Attribute::new_index("Test"));
-        assert_eq!(test, Attribute::from_str("Index(Test)"));
-        assert_ne!(test, Attribute::from_str("Test"));
+        assert_eq!(test, "Index(Test)".parse::<Attribute>().unwrap());
+        assert_ne!(test, "Test".parse::<Attribute>().unwrap());
         assert_ne!(test, Attribute::new_attribute("Test"));
     }
 }
@@ -60,8 +101,10 @@ impl bevy_inspector_egui::Inspectable for Attribute {
                     name.push(')');
                 }
             }
-            *self = Attribute::from_str(&name);
-            edit = true;
+            if let Ok(attribute) = name.parse() {
+                *self = attribute;
+                edit = true;
+            }
         }
         edit
     }
@@ -77,6 +120,10 @@ impl Attribute {
     pub const LAST_FPS: Attribute = Attribute(6);
     pub const INDEX: Attribute = Attribute(256);
 
+    pub fn as_u64(self) -> u64 {
+        self.0
+    }
+
     #[inline(always)]
     pub fn is_core(&self) -> bool {
         self.0 < 256
@@ -91,6 +138,79 @@ impl Attribute {
     }
 }
 
+/// An `Attribute` that is always `T`, so `AnimationState::get` and `AnimationState::set` are checked by the compiler
+pub struct TypedAttribute<T> {
+    attribute: Attribute,
+    name: &'static str,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for TypedAttribute<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for TypedAttribute<T> {}
+
+impl<T> TypedAttribute<T> {
+    /// a custom attribute with the same id as `Attribute::new_attribute(name)`;
+    /// the name is not kept until `register` is called
+    pub const fn new(name: &'static str) -> TypedAttribute<T> {
+        TypedAttribute { attribute: Attribute(Attribute::custom_id(name)), name, marker: PhantomData }
+    }
+
+    pub const fn attribute(&self) -> Attribute {
+        self.attribute
+    }
+
+    pub const fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// adds the name to `names`, such as `AnimationNodeTree::names_mut`, so it is shown in errors and saved files
+    /// or `NameCollision` if a different name already has the id
+    pub fn register(&self, names: &mut NameRegistry) -> Result<Attribute, BevySpriteAnimationError> {
+        if !self.attribute.is_core() {
+            names.insert_attribute(self.attribute, self.name)?;
+        }
+        Ok(self.attribute)
+    }
+}
+
+impl TypedAttribute<usize> {
+    pub const FRAMES: TypedAttribute<usize> = TypedAttribute { attribute: Attribute::FRAMES, name: "Frames", marker: PhantomData };
+
+    /// an index with the same id as `Attribute::new_index(name)`;
+    /// the name is not kept until `register` is called
+    pub const fn index(name: &'static str) -> TypedAttribute<usize> {
+        TypedAttribute { attribute: Attribute(Attribute::index_id(name)), name, marker: PhantomData }
+    }
+}
+
+impl TypedAttribute<f32> {
+    pub const DELTA: TypedAttribute<f32> = TypedAttribute { attribute: Attribute::DELTA, name: "Delta", marker: PhantomData };
+    pub const TIME_ON_FRAME: TypedAttribute<f32> = TypedAttribute { attribute: Attribute::TIME_ON_FRAME, name: "FrameTime", marker: PhantomData };
+    pub const LAST_FPS: TypedAttribute<f32> = TypedAttribute { attribute: Attribute::LAST_FPS, name: "LAST_FPS", marker: PhantomData };
+}
+
+impl TypedAttribute<bool> {
+    pub const FLIP_X: TypedAttribute<bool> = TypedAttribute { attribute: Attribute::FLIP_X, name: "FlipX", marker: PhantomData };
+    pub const FLIP_Y: TypedAttribute<bool> = TypedAttribute { attribute: Attribute::FLIP_Y, name: "FlipY", marker: PhantomData };
+}
+
+impl<T> From<TypedAttribute<T>> for Attribute {
+    fn from(typed: TypedAttribute<T>) -> Attribute {
+        typed.attribute
+    }
+}
+
+impl<T> std::fmt::Debug for TypedAttribute<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}<{}>", self.name, std::any::type_name::<T>())
+    }
+}
+
 impl std::fmt::Display for Attribute {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_str(&self.name_or_id())
@@ -121,6 +241,7 @@ impl Into<AttributeSerde> for &Attribute {
             3 => AttributeSerde::FrameTime,
             4 => AttributeSerde::FlipX,
             5 => AttributeSerde::FlipY,
+            6 => AttributeSerde::LastFPS,
             _ => panic!("Reserved for futer use")
             }
         } else if self.is_index() {
@@ -147,6 +268,7 @@ impl Into<Attribute> for AttributeSerde {
             AttributeSerde::Frames => Attribute::FRAMES,
             AttributeSerde::FlipX => Attribute::FLIP_X,
             AttributeSerde::FlipY => Attribute::FLIP_Y,
+            AttributeSerde::LastFPS => Attribute::LAST_FPS,
             AttributeSerde::AttributeName(name) => Attribute::new_attribute(&name),
             AttributeSerde::AttributeID(r) => Attribute(r),
         }
@@ -162,6 +284,7 @@ enum AttributeSerde {
     Frames,
     FlipX,
     FlipY,
+    LastFPS,
     AttributeID(u64),
     AttributeName(String),
 }
@@ -175,83 +298,91 @@ impl<'de> serde::Deserialize<'de> for Attribute {
     }
 }
 
-lazy_static::lazy_static! {
-    static ref CUSTOMATTRIBUTES: std::sync::Mutex<HashMap<Attribute, String>> = {
-        let mut map = HashMap::new();
-        map.insert(Attribute::NULL,             "null".to_string());
-        map.insert(Attribute::DELTA,            "Delta".to_string());
-        map.insert(Attribute::TIME_ON_FRAME,    "FrameTime".to_string());
-        map.insert(Attribute::FRAMES,           "Frames".to_string());
-        map.insert(Attribute::FLIP_X,           "FlipX".to_string());
-        map.insert(Attribute::FLIP_Y,           "FlipY".to_string());
-        map.insert(Attribute::LAST_FPS,         "LAST_FPS".to_string());
-        std::sync::Mutex::new(map)
-    };
-}
-
 impl Attribute {
 
     /// Returns the attribute for the given Index name
-    /// adds the name to the list of known names if it is not already there
     /// use from_str to get the attribute from a string
+    /// the name is only kept by the `AnimationNodeTree` loading it, use `try_new_index` to keep and check it yourself
     #[inline(always)]
     pub fn new_index(name: &str) -> Attribute{
+        let name = name.trim();
         let att = Attribute(Attribute::hash_for_index(name));
-        let mut map = CUSTOMATTRIBUTES.lock().unwrap();
-        if !map.contains_key(&att) {
-            map.insert(att, name.to_string());
-        }
+        crate::names::record_attribute(att, name);
         att
     }
 
     /// Returns the attribute for the given name
-    /// adds the name to the list of known names if it is not already there
     /// use from_str to get the attribute from a string
-
+    /// the name is only kept by the `AnimationNodeTree` loading it, use `try_new_attribute` to keep and check it yourself
     #[inline(always)]
     pub fn new_attribute(name: &str) -> Attribute{
-        let mut map = CUSTOMATTRIBUTES.lock().unwrap();
+        let name = name.trim();
         let att = Attribute(Attribute::hash_for_custom(name));
-        if !map.contains_key(&att) {
-            map.insert(att, name.to_string());
-        }
+        crate::names::record_attribute(att, name);
         att
     }
 
-    fn hash_for_custom(name: &str) -> u64 {
-        use std::hash::Hash;
-        use std::hash::Hasher;
-        let name = name.trim();
-        let mut hasher = std::collections::hash_map::DefaultHasher::default();
-        name.hash(&mut hasher);
-        let mut res = hasher.finish();
+    /// the index for `name`, adding it to `names` such as `AnimationNodeTree::names_mut`
+    /// or `NameCollision` if a different name already has the id
+    pub fn try_new_index(name: &str, names: &mut NameRegistry) -> Result<Attribute, BevySpriteAnimationError> {
+        names.attribute_index(name)
+    }
+
+    /// the custom attribute for `name`, adding it to `names` such as `AnimationNodeTree::names_mut`
+    /// or `NameCollision` if a different name already has the id
+    pub fn try_new_attribute(name: &str, names: &mut NameRegistry) -> Result<Attribute, BevySpriteAnimationError> {
+        names.attribute(name)
+    }
+
+    /// the id of a custom attribute; stable across Rust versions, see `StableHasher`
+    pub(crate) fn hash_for_custom(name: &str) -> u64 {
+        Attribute::custom_id(name.trim())
+    }
+
+    /// the id of an index attribute; stable across Rust versions, see `StableHasher`
+    pub(crate) fn hash_for_index(name: &str) -> u64{
+        Attribute::index_id(name.trim())
+    }
+
+    /// `hash_for_custom` without trimming `name`, so it can be used in `const`
+    const fn custom_id(name: &str) -> u64 {
+        let mut res = StableHasher::hash_const(name.as_bytes());
         while res < 65536 {
-            hasher.write_u8(0);
-            res = hasher.finish();
+            res = StableHasher::write_const(res, &[0]);
         }
         res
     }
 
-    fn hash_for_index(name: &str) -> u64{
-        let name = name.trim();
-        use std::hash::Hash;
-        use std::hash::Hasher;
-        let mut hasher = std::collections::hash_map::DefaultHasher::default();
-        name.hash(&mut hasher);
-        let mut res = hasher.finish() as u16;
-        while res < 256 {
-            hasher.write_u8(0);
-            res = hasher.finish() as u16;
+    /// `hash_for_index` without trimming `name`, so it can be used in `const`
+    const fn index_id(name: &str) -> u64 {
+        let mut res = StableHasher::hash_const(name.as_bytes());
+        while (res as u16) < 256 {
+            res = StableHasher::write_const(res, &[0]);
         }
-        res as u64
+        res as u16 as u64
+    }
+
+    /// the name of the attribute in `names`, such as `AnimationNodeTree::names`
+    pub fn name_in<'a>(&self, names: &'a NameRegistry) -> Option<&'a str> {
+        names.attribute_name(*self)
     }
 
-    /// Returns the name of the attribute or None if a custom attribute that is not in the list
+    /// Returns the name of a core attribute, or the name in the tree it is being shown by, see `AnimationNodeTree::display`
     pub fn name(&self) -> Option<String> {
-        if let Some(v) = CUSTOMATTRIBUTES.lock().unwrap().get(self) {
-            Some(v.clone())
-        } else {
-            None
+        self.core_name().map(str::to_string).or_else(|| crate::names::shown_attribute_name(*self))
+    }
+
+    /// the name of a core attribute, as written by `Display`
+    fn core_name(&self) -> Option<&'static str> {
+        match *self {
+            Attribute::NULL => Some("null"),
+            Attribute::DELTA => Some("Delta"),
+            Attribute::TIME_ON_FRAME => Some("FrameTime"),
+            Attribute::FRAMES => Some("Frames"),
+            Attribute::FLIP_X => Some("FlipX"),
+            Attribute::FLIP_Y => Some("FlipY"),
+            Attribute::LAST_FPS => Some("LAST_FPS"),
+            _ => None,
         }
     }
 
@@ -277,7 +408,7 @@ impl Attribute {
                     res.push_str(&format!("{:#06X}", self.0));
                     res.push(')');
                 } else {
-                    res.push_str(&format!("Attribute({:#018X})", self.0));
+                    res.push_str(&format!("{:#018X}", self.0));
                     res.push(')');
                 }
             }
@@ -289,66 +420,59 @@ impl Attribute {
     /// if the string has the form "Index(0x1234)" or "Attribute(0x12345678)"
     /// the attribute will be the corresponding attribute
     /// if the string has the form "Index('name')" or "Attribute('name')"
-    /// the attribute name will be added to the list of known names
-    /// will check if the str is a known attribute such as DELTA, TIME_ON_FRAME, FRAMES
-    pub fn from_str(from: &str) -> Attribute {
-        let from = from.trim();
-        if from.starts_with("Index(") {
-            if from.len() > 7 {
-            if let Some(c) = from.get(6..from.len() - 1) {
-                return if c.starts_with(|x: char| {x.is_digit(10)}) {
-                    Attribute::from_digit(c)
-                }
-                else {
-                    Attribute::new_index(c)
-                }
-            }
-            }
-            panic!("Invalid Index(...)")
-        }
-        if from.starts_with("Attribute(") {
-            if from.len() > 11 {
-            if let Some(c) = from.get(10..from.len() - 1) {
-                return if c.starts_with(|x: char| {x.is_digit(10)}) {
-                    Attribute::from_digit(&from[10..from.len() - 1])
-                }
-                else {
-                    Attribute::new_attribute(&from[10..from.len() - 1])
-                }
-            }
-            }
-            panic!("Invalid Attribute(...)")
-        }
-        if from.starts_with("Core(") {
-            return match &from[5..from.len() - 1] {
-                "Delta" => {Attribute::DELTA},
-                "FrameTime" => {Attribute::TIME_ON_FRAME},
-                "Frames" => {Attribute::FRAMES},
-                _ => panic!("Invalid Core(...)")
-            }
-        }
-        if from.starts_with(|c: char| {c.is_digit(10)}) {
-            return Attribute::from_digit(from);
-        }
-        match from {
-            "Delta" => {Attribute::DELTA},
-            "FrameTime" => {Attribute::TIME_ON_FRAME},
-            "Frames" => {Attribute::FRAMES},
-            _ => {Attribute::new_attribute(from)}
+    /// the core attribute called `name`, by its name or the name of its const
+    pub fn core(name: &str) -> Option<Attribute> {
+        match name.trim() {
+            "Delta" | "DELTA" => Some(Attribute::DELTA),
+            "FrameTime" | "TIME_ON_FRAME" => Some(Attribute::TIME_ON_FRAME),
+            "Frames" | "FRAMES" => Some(Attribute::FRAMES),
+            "FlipX" | "FLIP_X" => Some(Attribute::FLIP_X),
+            "FlipY" | "FLIP_Y" => Some(Attribute::FLIP_Y),
+            "LastFPS" | "LAST_FPS" => Some(Attribute::LAST_FPS),
+            _ => None,
         }
     }
+}
 
-    fn from_digit(data: &str) -> Attribute {
-        let data = data.trim();
-        if data.starts_with("0x") || data.starts_with("0X") {
-            return Attribute(u64::from_str_radix(&data[2..], 16).expect("proper hex format"));
+impl std::str::FromStr for Attribute {
+    type Err = BevySpriteAnimationError;
+
+    /// `Index(..)` or `Attribute(..)` with a name or number, `Core(..)` or the bare name of a core attribute,
+    /// a number, or the name of a custom attribute
+    fn from_str(from: &str) -> Result<Attribute, Self::Err> {
+        let from = from.trim();
+        let error = |message: String| BevySpriteAnimationError::ParseError { kind: "Attribute", input: from.to_string(), message };
+        let is_number = |s: &str| s.starts_with(|c: char| c.is_ascii_digit());
+        if let Some((kind, inner)) = from.strip_suffix(')').and_then(|from| from.split_once('(')) {
+            let inner = inner.trim();
+            if inner.is_empty() {
+                return Err(error(format!("`{}()` needs a name", kind)));
+            }
+            let id = if is_number(inner) {
+                Some(crate::hash::parse_u64(inner).ok_or_else(|| error(format!("invalid number `{}`", inner)))?)
+            } else {
+                None
+            };
+            return match (kind.trim(), id) {
+                ("Index", Some(id)) if Attribute(id).is_index() => Ok(Attribute(id)),
+                ("Index", Some(id)) => Err(error(format!("{:#X} is not an index id", id))),
+                ("Index", None) => Ok(Attribute::new_index(inner)),
+                ("Attribute", Some(id)) if Attribute(id).is_custom() => Ok(Attribute(id)),
+                ("Attribute", Some(id)) => Err(error(format!("{:#X} is not a custom attribute id", id))),
+                ("Attribute", None) => Ok(Attribute::new_attribute(inner)),
+                ("Core", _) => Attribute::core(inner).ok_or_else(|| error(format!("unknown core attribute `{}`", inner))),
+                _ => Err(error("expected `Index(..)`, `Attribute(..)` or `Core(..)`".to_string())),
+            };
         }
-        if data.starts_with("0b") || data.starts_with("0B") {
-            return Attribute(u64::from_str_radix(&data[2..], 2).expect("proper binary format"));
+        if from.is_empty() {
+            return Err(error("needs a name".to_string()));
         }
-        if data.starts_with("0o") || data.starts_with("0O") {
-            return Attribute(u64::from_str_radix(&data[2..], 8).expect("proper octal format"));
+        if is_number(from) {
+            return crate::hash::parse_u64(from).map(Attribute).ok_or_else(|| error("invalid number".to_string()));
         }
-        Attribute(u64::from_str_radix(data, 10).expect("proper decimal format"))
+        if from.contains(['(', ')']) {
+            return Err(error("unmatched bracket".to_string()));
+        }
+        Ok(Attribute::core(from).unwrap_or_else(|| Attribute::new_attribute(from)))
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,64 @@
+use bevy::asset::{AssetServer, FileAssetIo};
+use bevy::core::TaskPoolOptions;
+use bevy_sprite_animation::prelude::*;
+
+const USAGE: &str = "usage:
+    node_names dump <out.names> <file.node | file.nodetree>...
+        loads each file and writes every node and attribute name they use to <out.names>
+    node_names show <file.names> [id]...
+        prints the name of each id, or every line of the file if no ids are given";
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let res = match args.first().map(String::as_str) {
+        Some("dump") if args.len() >= 3 => dump(&args[1], &args[2..]),
+        Some("show") if args.len() >= 2 => show(&args[1], &args[2..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            std::process::exit(2);
+        },
+    };
+    if let Err(e) = res {
+        eprintln!("{}", e);
+        std::process::exit(1);
+    }
+}
+
+/// loads `files` into a tree and saves its names to `out`
+/// files that can not be loaded, such as ones using a node type only a game registers, are skipped with a warning
+fn dump(out: &str, files: &[String]) -> Result<(), BevySpriteAnimationError> {
+    TaskPoolOptions::default().create_default_pools();
+    let asset_server = AssetServer::new(FileAssetIo::new("assets", &None));
+    let mut tree = AnimationNodeTree::<()>::default();
+    for file in files {
+        let data = std::fs::read_to_string(file)?;
+        let res = if file.ends_with(".nodetree") {
+            tree.load_tree_from_str(&data, &asset_server).map(|_| ())
+        } else {
+            tree.load_node_from_str(&data, &asset_server).map(|_| ())
+        };
+        if let Err(e) = res {
+            eprintln!("skipped {}: {}", file, e);
+        }
+    }
+    tree.save_names(out)?;
+    println!("wrote {} node and {} attribute names to {}", tree.names().nodes().count(), tree.names().attributes().filter(|(att, _)| !att.is_core()).count(), out);
+    Ok(())
+}
+
+/// prints the names of `ids` from the names file at `path`
+fn show(path: &str, ids: &[String]) -> Result<(), BevySpriteAnimationError> {
+    let names: NameRegistry = std::fs::read_to_string(path)?.parse()?;
+    if ids.is_empty() {
+        print!("{}", names);
+        return Ok(());
+    }
+    for id in ids {
+        // a bare number could be a node or an attribute so both are checked
+        let node = if id.starts_with("Index") || id.starts_with("Attribute") {None} else {id.parse::<NodeID>().ok()};
+        let attribute = if id.starts_with("NodeID") {None} else {id.parse::<Attribute>().ok()};
+        let name = node.and_then(|node| names.node_name(node)).or_else(|| attribute.and_then(|att| names.attribute_name(att)));
+        println!("{} -> {}", id, name.unwrap_or("unknown"));
+    }
+    Ok(())
+}
@@ -0,0 +1,202 @@
+use bevy::prelude::*;
+
+use crate::error::BevySpriteAnimationError as Error;
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_section() {
+        let (defaults, rest) = parse_attributes("attributes: {
+            Index(defaults_fall): 0 temp,
+            Index(defaults_stand): persist,
+            Attribute(defaults_name): (a: \"b, c}\"),
+            Attribute(defaults_quote): \"say \\\"hi}\\\", \\\\\",
+        },
+        [FPSNode(name: \"defaults\", fps: 7, then: NodeID(\"0x1\"),),]").unwrap().unwrap();
+        assert_eq!(defaults, vec![
+            (Attribute::new_index("defaults_fall"), AttributeDefault { value: Some("0".to_string()), temporary: Some(true) }),
+            (Attribute::new_index("defaults_stand"), AttributeDefault { value: None, temporary: Some(false) }),
+            (Attribute::new_attribute("defaults_name"), AttributeDefault { value: Some("(a: \"b, c}\")".to_string()), temporary: None }),
+            (Attribute::new_attribute("defaults_quote"), AttributeDefault { value: Some("\"say \\\"hi}\\\", \\\\\"".to_string()), temporary: None }),
+        ]);
+        let value: String = ron::from_str(defaults[3].1.value.as_ref().unwrap()).unwrap();
+        assert_eq!(value, "say \"hi}\", \\");
+        assert!(rest.trim().starts_with("[FPSNode"));
+        assert!(parse_attributes("[FPSNode()]").unwrap().is_none());
+        assert!(parse_attributes("attributes: { Index(defaults_fall) 0 }").is_err());
+        assert!(parse_attributes("attributes: { Index(defaults_fall): 0 ").is_err());
+        assert!(parse_attributes("attributes: { Index(0xZZ): 0 }").is_err());
+        assert!(parse_attributes("attributes: { Core(Speed): 0 }").is_err());
+    }
+
+    #[test]
+    fn apply_keeps_set_values() {
+        let fall = Attribute::new_index("defaults_fall");
+        let mut state = AnimationState::default();
+        AttributeDefault { value: Some("2".to_string()), temporary: Some(true) }.apply(fall, &mut state).unwrap();
+        assert_eq!(state.get_attribute::<usize>(&fall), 2);
+        assert!(state.temp.contains(&fall));
+        state.set_attribute(fall, 5usize);
+        AttributeDefault { value: Some("2".to_string()), temporary: Some(false) }.apply(fall, &mut state).unwrap();
+        assert_eq!(state.get_attribute::<usize>(&fall), 5);
+        assert!(!state.temp.contains(&fall));
+        assert!(AttributeDefault { value: Some("true".to_string()), temporary: None }.apply(Attribute::new_index("defaults_bad"), &mut state).is_err());
+    }
+
+    #[test]
+    fn tree_defaults_are_applied() {
+        #[derive(Component)]
+        struct DefaultsFlag;
+        let asset_server = crate::test::test_asset_server();
+        let mut tree = AnimationNodeTree::<DefaultsFlag>::default();
+        tree.load_tree_from_str("attributes: { Index(defaults_tree): 3 temp, FlipX: true }, [
+            FPSNode(name: \"defaults_tree\", fps: 7, then: NodeID(\"0x1\"),),
+        ]", &asset_server).unwrap();
+        let mut app = App::new();
+        app.insert_resource(tree);
+        app.add_systems(Update, apply_attribute_defaults::<DefaultsFlag>);
+        let entity = app.world.spawn((DefaultsFlag, AnimationState::default())).id();
+        let mut flipped = AnimationState::default();
+        flipped.set(TypedAttribute::FLIP_X, false);
+        let flipped = app.world.spawn((DefaultsFlag, flipped)).id();
+        app.update();
+        let state = app.world.get::<AnimationState>(entity).unwrap();
+        assert_eq!(state.get_attribute::<usize>(&Attribute::new_index("defaults_tree")), 3);
+        assert!(state.temp.contains(&Attribute::new_index("defaults_tree")));
+        assert!(state.get_attribute::<bool>(&Attribute::FLIP_X));
+        // a flip set when the state was spawned is kept like any other attribute
+        assert!(!app.world.get::<AnimationState>(flipped).unwrap().get(TypedAttribute::FLIP_X));
+    }
+}
+
+/// The starting value of an attribute and if it is temporary, from the `attributes:` section of a `.nodetree`
+/// written as `Index(Fall): 0 temp`, `Attribute(ZombieState): Idle persist` or `Index(Stand): temp`
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeDefault {
+    /// ron for the attribute's type; custom attributes need their type registered with `AnimationNodeTree::register_attribute`
+    pub value: Option<String>,
+    /// `temp` is Some(true) and `persist` Some(false)
+    pub temporary: Option<bool>,
+}
+
+impl AttributeDefault {
+    /// sets the value if `state` does not have the attribute yet and sets it temporary or persistent
+    pub fn apply(&self, attribute: Attribute, state: &mut AnimationState) -> Result<(), Error> {
+        if state.try_get_attribute_value(&attribute).is_none() {
+            self.set_value(attribute, state)?;
+        }
+        match self.temporary {
+            Some(true) => {state.set_temporary(attribute);},
+            Some(false) => {state.set_persistent(&attribute);},
+            None => {},
+        }
+        Ok(())
+    }
+
+    fn set_value(&self, attribute: Attribute, state: &mut AnimationState) -> Result<(), Error> {
+        if let Some(value) = self.value.as_deref() {
+            state.set_attribute_from_ron(attribute, value)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::fmt::Display for AttributeDefault {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let flag = match self.temporary {
+            Some(true) => Some("temp"),
+            Some(false) => Some("persist"),
+            None => None,
+        };
+        match (&self.value, flag) {
+            (Some(value), Some(flag)) => write!(f, "{} {}", value, flag),
+            (Some(value), None) => f.write_str(value),
+            (None, Some(flag)) => f.write_str(flag),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+type Entries = Vec<(Attribute, AttributeDefault)>;
+
+/// the entries of an `attributes: { .. }` section at the start of `data` and the data after it;
+/// None if `data` does not start with one
+pub(crate) fn parse_attributes(data: &str) -> Result<Option<(Entries, &str)>, Error> {
+    let Some(rest) = data.trim_start().strip_prefix("attributes:") else {return Ok(None);};
+    let Some(rest) = rest.trim_start().strip_prefix('{') else {
+        return Err(Error::MalformedStr { message: "expected `{` after `attributes:`".to_string(), location: crate::here!() });
+    };
+    let mut entries = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut escaped = false;
+    let mut start = 0;
+    for (i, c) in rest.char_indices() {
+        match c {
+            // a `\` in a string escapes the next char so `\"` does not end it
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            '(' | '[' | '{' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            '}' if !in_string && depth > 0 => depth -= 1,
+            ',' | '}' if !in_string && depth == 0 => {
+                let entry = rest[start..i].trim();
+                if !entry.is_empty() {
+                    entries.push(parse_entry(entry)?);
+                }
+                start = i + 1;
+                if c == '}' {
+                    let rest = rest[i + 1..].trim_start();
+                    return Ok(Some((entries, rest.strip_prefix(',').unwrap_or(rest))));
+                }
+            },
+            _ => {},
+        }
+    }
+    Err(Error::MalformedStr { message: "`attributes: {` is never closed".to_string(), location: crate::here!() })
+}
+
+/// `Attribute: value flag`
+fn parse_entry(entry: &str) -> Result<(Attribute, AttributeDefault), Error> {
+    let malformed = |message: String| Error::MalformedStr { message, location: crate::here!() };
+    // a key in brackets ends at its `)` so a `:` in the name is not taken as the end
+    let end = match entry.find('(') {
+        Some(open) if entry[..open].chars().all(char::is_alphanumeric) => entry[open..].find(')').map(|close| open + close + 1),
+        _ => entry.find(':'),
+    };
+    let Some((key, value)) = end.and_then(|end| Some((&entry[..end], entry[end..].trim_start().strip_prefix(':')?.trim()))) else {
+        return Err(malformed(format!("expected `Attribute: value` in attributes, found `{}`", entry)));
+    };
+    let attribute = key.parse()?;
+    let (value, temporary) = match value.rsplit_once(char::is_whitespace).unwrap_or(("", value)) {
+        (rest, "temp" | "temporary") => (rest.trim(), Some(true)),
+        (rest, "persist" | "persistent") => (rest.trim(), Some(false)),
+        _ => (value, None),
+    };
+    let value = (!value.is_empty()).then(|| value.to_string());
+    Ok((attribute, AttributeDefault { value, temporary }))
+}
+
+/// sets the `attributes:` of the tree's files on each state the first time it is seen and again when the files change
+pub(crate) fn apply_attribute_defaults<F: Component>(
+    tree: Res<AnimationNodeTree<F>>,
+    mut states: Query<&mut AnimationState, With<F>>,
+) {
+    for mut state in states.iter_mut() {
+        if state.defaults == tree.defaults_generation {
+            continue;
+        }
+        let state = state.as_mut();
+        state.defaults = tree.defaults_generation;
+        state.attribute_types = tree.attribute_types.clone();
+        for (attribute, default) in tree.attribute_defaults.iter() {
+            if let Err(e) = default.apply(*attribute, state) {
+                error!("failed to set {} to {}: {}", tree.display(attribute), default, tree.display(&e));
+            }
+        }
+    }
+}
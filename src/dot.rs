@@ -0,0 +1,86 @@
+use std::fmt::Write;
+
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fps_to_index() {
+        let mut tree = AnimationNodeTree::<()>::default();
+        let index = tree.add_node(Box::new(IndexNode::new::<Frame>("dot_index", &[], true)));
+        tree.add_node(Box::new(FPSNode::new("dot_fps", 7, index)));
+        let dot = tree.to_dot();
+        assert!(dot.starts_with("digraph AnimationNodeTree {"));
+        assert!(dot.contains("label=\"dot_index\\nIndexNode\""));
+        assert!(dot.contains("label=\"dot_fps\\nFPSNode\""));
+        assert!(dot.contains(&format!("{} -> {} [label=\"then\"];", dot_id(NodeID::from_name("dot_fps")), dot_id(index))));
+    }
+
+    #[test]
+    fn short_type_names() {
+        assert_eq!(short_type("MatchNode<zombie::animation::ZState>"), "MatchNode<ZState>");
+        assert_eq!(short_type("FPSNode"), "FPSNode");
+    }
+}
+
+impl<F> AnimationNodeTree<F> {
+    /// The nodes of the tree as a Graphviz DOT graph
+    /// nodes are labelled with their name and type, edges with `AnimationNode::edges` labels
+    /// nodes that are gone to but not in the tree are drawn dashed
+    pub fn to_dot(&self) -> String {
+        crate::names::with_names(&self.names, || self.write_dot())
+    }
+
+    fn write_dot(&self) -> String {
+        let mut ids: Vec<&NodeID> = self.nodes.keys().collect();
+        ids.sort_by_key(|id| id.as_u64());
+
+        let mut dot = String::from("digraph AnimationNodeTree {\n");
+        let mut missing = Vec::new();
+        for id in ids.iter() {
+            let node = &self.nodes[id];
+            let label = format!("{}\n{}", id.name_or_id(), short_type(&node.node_type()));
+            let _ = writeln!(dot, "    {} [label=\"{}\"];", dot_id(**id), escape(&label));
+        }
+        for id in ids {
+            for edge in self.nodes[id].edges() {
+                if !self.nodes.contains_key(&edge.next) && !missing.contains(&edge.next) {
+                    missing.push(edge.next);
+                }
+                let _ = writeln!(dot, "    {} -> {} [label=\"{}\"];", dot_id(*id), dot_id(edge.next), escape(&edge.label));
+            }
+        }
+        for id in missing {
+            let _ = writeln!(dot, "    {} [label=\"{}\\nmissing\", style=dashed, color=red];", dot_id(id), escape(&id.name_or_id()));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn dot_id(id: NodeID) -> String {
+    format!("n{:016x}", id.as_u64())
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+/// drops the module path from every type in `type_name`
+fn short_type(type_name: &str) -> String {
+    let mut short = String::new();
+    let mut word = String::new();
+    for c in type_name.chars() {
+        if c.is_alphanumeric() || c == '_' || c == ':' {
+            word.push(c);
+        } else {
+            short.push_str(word.rsplit("::").next().unwrap_or_default());
+            word.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(word.rsplit("::").next().unwrap_or_default());
+    short
+}
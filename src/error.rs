@@ -1,5 +1,6 @@
 use bevy::prelude::Handle;
 use bevy::prelude::Image;
+use bevy::prelude::TextureAtlas;
 use thiserror::Error;
 
 use crate::prelude::NodeID;
@@ -23,10 +24,16 @@ pub enum BevySpriteAnimationError {
     #[cfg(feature = "ron")]
     #[error("RonError: {0}")]
     RonDeError(#[from] ron::de::SpannedError),
-    #[error("{0} Not Found")]
+    #[error("{} Not Found", .0.name_or_id())]
     NodeNotFound(NodeID),
     #[error("{} Not Found", .0.name_or_id())]
     AttributeNotFound(Attribute),
+    #[error("{attribute} is a {found} not a {expected}")]
+    AttributeType{
+        attribute: Attribute,
+        expected: &'static str,
+        found: &'static str,
+    },
     #[error("a BincodeError orccored")]
     BincodeError(#[from] bincode::Error),
     #[error("Node Error: {0}")]
@@ -50,14 +57,35 @@ pub enum BevySpriteAnimationError {
     #[error("No AssetPath found for {:?}: only loaded asses are suported for now",.0)]
     AssetPathNotFound(Handle<Image>),
     #[cfg(feature = "serialize")]
+    #[error("No SpriteSheet found for {:?}: only atlases built from a SpriteSheet can be serialized",.0)]
+    SpriteSheetNotFound(Handle<TextureAtlas>),
+    #[cfg(feature = "serialize")]
     #[error("Failed to parse int: {0}")]
     ParseIntError(#[from] std::num::ParseIntError),
     #[cfg(feature = "serialize")]
     #[error("Failed to parse float: {0}")]
     ParseFloatError(#[from] std::num::ParseFloatError),
     #[cfg(feature = "ron")]
-    #[error("Failed to find typeid for: {0};\n you must set a attribute once before a script node can set it")]
+    #[error("Failed to find typeid for: {0};\n set the attribute once, give it a value in the tree's `attributes:` or use `register_attribute` before reading it from ron")]
     NoTypeId(Attribute),
+    #[error("invalid {kind} `{input}`: {message}")]
+    ParseError{
+        kind: &'static str,
+        input: String,
+        message: String,
+    },
+    #[error("{name:?} hashes to {id:#X} which is already used by {existing:?}")]
+    NameCollision{
+        name: String,
+        existing: String,
+        id: u64,
+    },
+    #[error("{message} at line {line} column {column}")]
+    ScriptError{
+        message: String,
+        line: usize,
+        column: usize,
+    },
     #[error("asset IO")]
     AssetIo(#[from]bevy::asset::AssetIoError),
     #[error("bytes to string err")]
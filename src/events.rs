@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+
+use crate::prelude::NodeID;
+
+/// Sent when an animation reaches a frame with a marker on it
+/// every marker passed in one update is sent even if the node skipped over its frame
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct AnimationMarker {
+    pub entity: Entity,
+    /// the node the marker is on
+    pub node: NodeID,
+    pub marker: String,
+    pub frame: usize,
+}
+
+/// Sent when a `ScriptNode` runs an `emit name` or `emit name(payload)` statement
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct ScriptEvent {
+    pub entity: Entity,
+    /// the script that sent it
+    pub node: NodeID,
+    pub name: String,
+    /// the ron between the brackets after the name, `()` if there were none
+    pub payload: String,
+}
+
+#[cfg(feature = "ron")]
+impl ScriptEvent {
+    pub fn payload<T: serde::de::DeserializeOwned>(&self) -> Result<T, ron::error::SpannedError> {
+        ron::from_str(&self.payload)
+    }
+}
+
+/// Sent when running the node tree for an entity fails
+#[derive(Event, Debug, Clone, PartialEq)]
+pub struct AnimationError {
+    pub entity: Entity,
+    pub kind: AnimationErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum AnimationErrorKind {
+    /// a node was reached twice in one update;
+    /// holds the nodes in the cycle starting and ending with the node that was reached twice
+    Cycle(Vec<NodeID>),
+    /// more then `AnimationNodeTree::max_depth` nodes were run in one update; holds every node that was run
+    MaxDepth(Vec<NodeID>),
+    NodeNotFound(NodeID),
+    /// a node returned `NodeResult::Error`
+    NodeError(NodeID, String),
+}
+
+impl std::fmt::Display for AnimationErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AnimationErrorKind::Cycle(path) => write!(f, "Cycle found: {}", path_to_string(path)),
+            AnimationErrorKind::MaxDepth(path) => write!(f, "Max depth of {} reached: {}", path.len(), path_to_string(path)),
+            AnimationErrorKind::NodeNotFound(id) => write!(f, "Node not found: {}", id.name_or_id()),
+            AnimationErrorKind::NodeError(id, e) => write!(f, "{}: {}", id.name_or_id(), e),
+        }
+    }
+}
+
+fn path_to_string(path: &[NodeID]) -> String {
+    path.iter().map(|id| id.name_or_id()).collect::<Vec<_>>().join(" -> ")
+}
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+use std::hash::Hasher;
+
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn fnv_known_values() {
+        let mut hasher = StableHasher::default();
+        assert_eq!(hasher.finish(), 0xCBF29CE484222325);
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xAF63DC4C8601EC8C);
+        let mut hasher = StableHasher::default();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.finish(), 0x85944171F73967E8);
+        assert_eq!(StableHasher::hash_const(b"foobar"), 0x85944171F73967E8);
+    }
+
+    #[test]
+    fn ids_do_not_change() {
+        assert_eq!(NodeID::from_name("Zombie1_Idle").as_u64(), 0x96F666EC10FE231D);
+        assert_eq!(Attribute::new_attribute("ZombieState"), "Attribute(0xB02CAD5977026CD0)".parse::<Attribute>().unwrap());
+        assert_eq!(Attribute::new_index("Stand"), "Index(0xE4C3)".parse::<Attribute>().unwrap());
+    }
+
+    #[test]
+    fn migrate_node_ids() {
+        let old = legacy::node_id("migrate_node");
+        let new = NodeID::from_name("migrate_node");
+        let data = format!("{} then: (\"{:#018X}\")", old, old.as_u64());
+        let migrated = migrate_ids(&data, ["migrate_node"]);
+        assert_eq!(migrated, format!("{} then: (\"{:#018X}\")", new, new.as_u64()));
+    }
+
+    #[test]
+    fn migrate_index_ids() {
+        let old = legacy::index("migrate_index");
+        let new = Attribute::new_index("migrate_index");
+        let data = format!("index: IndexID({}), if Index({:#06X})", old.as_u64(), old.as_u64());
+        let migrated = migrate_ids(&data, ["migrate_index"]);
+        assert_eq!(migrated, format!("index: IndexID({}), if Index({:#06X})", new.as_u64(), new.as_u64()));
+    }
+}
+
+/// parses an id in decimal, hex (0x), binary (0b) or octal (0o)
+pub(crate) fn parse_u64(data: &str) -> Option<u64> {
+    let data = data.trim();
+    let (digits, radix) = match data.get(..2) {
+        Some("0x" | "0X") => (&data[2..], 16),
+        Some("0b" | "0B") => (&data[2..], 2),
+        Some("0o" | "0O") => (&data[2..], 8),
+        _ => (data, 10),
+    };
+    u64::from_str_radix(digits, radix).ok()
+}
+
+/// The hasher used for `NodeID` and `Attribute` names
+/// 64 bit FNV-1a, so ids saved in files stay the same across Rust versions and platforms
+pub struct StableHasher(u64);
+
+impl StableHasher {
+    const OFFSET_BASIS: u64 = 0xCBF29CE484222325;
+    const PRIME: u64 = 0x100000001B3;
+
+    /// `write` as a const fn, so ids can be made in `const`
+    pub(crate) const fn write_const(mut hash: u64, bytes: &[u8]) -> u64 {
+        let mut i = 0;
+        while i < bytes.len() {
+            hash ^= bytes[i] as u64;
+            hash = hash.wrapping_mul(StableHasher::PRIME);
+            i += 1;
+        }
+        hash
+    }
+
+    /// the hash of `bytes` written to a new hasher
+    pub(crate) const fn hash_const(bytes: &[u8]) -> u64 {
+        StableHasher::write_const(StableHasher::OFFSET_BASIS, bytes)
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        StableHasher(StableHasher::OFFSET_BASIS)
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.0 = StableHasher::write_const(self.0, bytes);
+    }
+}
+
+/// The ids names hashed to before `StableHasher`
+/// these used the std `DefaultHasher` so may not match files saved with a different Rust version
+pub mod legacy {
+    use std::hash::{Hash, Hasher};
+    use std::collections::hash_map::DefaultHasher;
+
+    use crate::prelude::*;
+
+    pub fn node_id(name: &str) -> NodeID {
+        let mut hasher = DefaultHasher::default();
+        name.trim().hash(&mut hasher);
+        while hasher.finish() < 65536 {
+            hasher.write_u8(0);
+        }
+        NodeID::from_u64(hasher.finish())
+    }
+
+    pub fn attribute(name: &str) -> Attribute {
+        let mut hasher = DefaultHasher::default();
+        name.trim().hash(&mut hasher);
+        while hasher.finish() < 65536 {
+            hasher.write_u8(0);
+        }
+        format!("Attribute({})", hasher.finish()).parse().expect("a hashed id to be an attribute")
+    }
+
+    pub fn index(name: &str) -> Attribute {
+        let mut hasher = DefaultHasher::default();
+        name.trim().hash(&mut hasher);
+        while (hasher.finish() as u16) < 256 {
+            hasher.write_u8(0);
+        }
+        format!("Index({})", hasher.finish() as u16).parse().expect("a hashed id to be an index")
+    }
+}
+
+/// Rewrites the legacy ids of `names` in the contents of a `.node`, `.nodetree` or names file to their current ids
+/// `names` should be every node and attribute name the file uses, as the old ids can not be turned back into names;
+/// hex ids keep their width and `IndexID(..)` keeps its decimal form
+/// the old ids are hashed again with the `DefaultHasher` of the toolchain running this, which Rust does not keep stable,
+/// so run it with the Rust version that saved the files or the old ids may not be found
+pub fn migrate_ids<'a>(data: &str, names: impl IntoIterator<Item = &'a str>) -> String {
+    let mut ids = HashMap::new();
+    let mut indices = HashMap::new();
+    for name in names {
+        ids.insert(legacy::node_id(name).as_u64(), NodeID::from_name(name).as_u64());
+        ids.insert(legacy::attribute(name).as_u64(), Attribute::new_attribute(name).as_u64());
+        indices.insert(legacy::index(name).as_u64(), Attribute::new_index(name).as_u64());
+    }
+
+    let mut res = String::with_capacity(data.len());
+    let mut rest = data;
+    while let Some(start) = rest.find(|c: char| c.is_ascii_digit()) {
+        let (before, number) = rest.split_at(start);
+        res.push_str(before);
+        let hex = number.starts_with("0x") || number.starts_with("0X");
+        let digits = if hex {&number[2..]} else {number};
+        let len = digits.find(|c: char| !c.is_ascii_hexdigit()).unwrap_or(digits.len());
+        let end = if hex {len + 2} else {digits.find(|c: char| !c.is_ascii_digit()).unwrap_or(digits.len())};
+        let word = &number[..end];
+        rest = &number[end..];
+        let migrated = if hex {
+            u64::from_str_radix(&digits[..len], 16).ok().and_then(|id| {
+                ids.get(&id).or_else(|| indices.get(&id)).map(|new| {
+                    let upper = !digits[..len].contains(|c: char| c.is_ascii_lowercase());
+                    let new = if upper {format!("{:0width$X}", new, width = len)} else {format!("{:0width$x}", new, width = len)};
+                    format!("{}{}", &number[..2], new)
+                })
+            })
+        } else if before.ends_with("IndexID(") {
+            word.parse::<u64>().ok().and_then(|id| indices.get(&id)).map(|new| new.to_string())
+        } else {
+            None
+        };
+        // skip the rest of a word so names like `Zombie1` are left alone
+        if migrated.is_none() && before.ends_with(|c: char| c.is_alphanumeric() || c == '_') {
+            let tail = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+            res.push_str(word);
+            res.push_str(&rest[..tail]);
+            rest = &rest[tail..];
+            continue;
+        }
+        res.push_str(migrated.as_deref().unwrap_or(word));
+    }
+    res.push_str(rest);
+    res
+}
@@ -2,41 +2,266 @@ use bevy::prelude::*;
 use node_core::NodeLoader;
 use node_core::CanLoad;
 use crate::error::BevySpriteAnimationError as Error;
-use std::{collections::HashMap, marker::PhantomData};
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
 use crate::prelude::*;
+#[cfg(feature = "serialize")]
+use crate::asset::NodeTreeAsset;
 
 mod error;
 
 pub mod prelude;
 
+#[cfg(feature = "serialize")]
+pub mod asset;
 pub mod attributes;
+#[cfg(feature = "serialize")]
+pub mod defaults;
+#[cfg(feature = "dot")]
+pub mod dot;
+pub mod events;
+pub mod hash;
+pub mod names;
 pub mod node_core;
 pub mod nodes;
+pub mod sprite_sheet;
 pub mod state;
 pub mod system_set;
+pub mod validate;
 
 #[cfg(test)]
 mod test{
+    use bevy::prelude::*;
+    use crate::prelude::*;
+
     pub(crate) fn test_asset_server() -> bevy::asset::AssetServer {
         use bevy::core::TaskPoolOptions;
         TaskPoolOptions::default().create_default_pools();
         bevy::asset::AssetServer::new(bevy::asset::FileAssetIo::new("assets", &None))
     }
+
+    /// runs `f` with `indices` shown by name like a tree that loaded them, for tests that check `Display`
+    pub(crate) fn with_indices<T>(indices: &[&str], f: impl FnOnce() -> T) -> T {
+        let mut names = NameRegistry::default();
+        for name in indices {
+            names.attribute_index(name).unwrap();
+        }
+        crate::names::with_names(&std::sync::Arc::new(names), f)
+    }
+
+    #[derive(Component)]
+    struct TestFlag;
+
+    fn run_tree(tree: AnimationNodeTree<TestFlag>, start: NodeID) -> Vec<AnimationErrorKind> {
+        let mut app = App::new();
+        app.add_event::<AnimationMarker>();
+        app.add_event::<ScriptEvent>();
+        app.add_event::<AnimationError>();
+        app.insert_resource(tree);
+        app.add_systems(Update, super::animation_system::<TestFlag>);
+        app.world.spawn((TestFlag, AnimationState::default(), StartNode::from_nodeid(start)));
+        app.update();
+        let events = app.world.resource::<Events<AnimationError>>();
+        events.iter_current_update_events().map(|e| e.kind.clone()).collect()
+    }
+
+    #[test]
+    fn cycle_is_reported() {
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        let a = tree.add_node(Box::new(FPSNode::new("cycle_a", 7, NodeID::from_name("cycle_b"))));
+        let b = tree.add_node(Box::new(FPSNode::new("cycle_b", 7, NodeID::from_name("cycle_c"))));
+        let c = tree.add_node(Box::new(FPSNode::new("cycle_c", 7, b)));
+        assert_eq!(run_tree(tree, a), vec![AnimationErrorKind::Cycle(vec![b, c, b])]);
+    }
+
+    #[test]
+    #[cfg(feature = "ron")]
+    fn script_events_are_sent() {
+        #[derive(serde::Deserialize, Debug, PartialEq)]
+        struct Hit {
+            damage: u32,
+        }
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        let script = tree.add_node(Box::new(ScriptNode::new("#name script_events emit hit((damage: 5)) return NodeID(script_events_end)")));
+        let mut app = App::new();
+        app.add_event::<AnimationMarker>();
+        app.add_event::<ScriptEvent>();
+        app.add_event::<AnimationError>();
+        app.insert_resource(tree);
+        app.add_systems(Update, super::animation_system::<TestFlag>);
+        let entity = app.world.spawn((TestFlag, AnimationState::default(), StartNode::from_nodeid(script))).id();
+        app.update();
+        let events: Vec<ScriptEvent> = app.world.resource::<Events<ScriptEvent>>().iter_current_update_events().cloned().collect();
+        assert_eq!(events.len(), 1);
+        assert_eq!((events[0].entity, events[0].node, events[0].name.as_str()), (entity, script, "hit"));
+        assert_eq!(events[0].payload::<Hit>().unwrap(), Hit { damage: 5 });
+    }
+
+    #[test]
+    #[cfg(feature = "ron")]
+    fn registered_attributes_read_ron() {
+        #[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq)]
+        enum Mood {
+            Calm,
+            Angry,
+        }
+        let mood = Attribute::new_attribute("registered_mood");
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        tree.register_attribute::<Mood>(mood);
+        let script = tree.add_node(Box::new(ScriptNode::new("#name registered_script
+            if Attribute(registered_mood) == none { set Attribute(registered_mood) = Ron(Calm) }
+            else if Attribute(registered_mood) == Ron(Calm) { set Attribute(registered_mood) = Ron(Angry) }
+            return NodeID(registered_end)")));
+        let mut app = App::new();
+        app.add_event::<AnimationMarker>();
+        app.add_event::<ScriptEvent>();
+        app.add_event::<AnimationError>();
+        app.insert_resource(tree);
+        app.add_systems(Update, super::animation_system::<TestFlag>);
+        let entity = app.world.spawn((TestFlag, AnimationState::default(), StartNode::from_nodeid(script))).id();
+        app.update();
+        assert_eq!(app.world.get::<AnimationState>(entity).unwrap().get_attribute::<Mood>(&mood), Mood::Calm);
+        app.update();
+        let state = app.world.get::<AnimationState>(entity).unwrap();
+        assert_eq!(state.get_attribute::<Mood>(&mood), Mood::Angry);
+        assert!(AnimationState::default().set_attribute_from_ron(mood, "Calm").is_err());
+    }
+
+    #[test]
+    fn max_depth_is_reported() {
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        tree.set_max_depth(2);
+        let a = tree.add_node(Box::new(FPSNode::new("depth_a", 7, NodeID::from_name("depth_b"))));
+        let b = tree.add_node(Box::new(FPSNode::new("depth_b", 7, NodeID::from_name("depth_c"))));
+        tree.add_node(Box::new(FPSNode::new("depth_c", 7, NodeID::from_name("depth_d"))));
+        assert_eq!(run_tree(tree, a), vec![AnimationErrorKind::MaxDepth(vec![a, b])]);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn bad_ids_are_load_errors() {
+        let asset_server = test_asset_server();
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        assert!(tree.load_tree_from_str("[NodeID(\"0xZZ\"):FPSNode(name: \"bad_id\", fps: 7, then: NodeID(\"0x1\"),),]", &asset_server).is_err());
+        assert!(tree.load_tree_from_str("[FPSNode(name: \"bad_then\", fps: 7, then: NodeID(\"0x\"),),]", &asset_server).is_err());
+        assert!(tree.load_tree_from_str("[NodeID(\"0x1\")", &asset_server).is_err());
+        assert!(tree.load_tree_from_str("attributes: { Index(): 0 }, []", &asset_server).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn names_are_per_tree() {
+        let asset_server = test_asset_server();
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        let ids = tree.load_tree_from_str("attributes: { Index(tree_named_index): 0 }, [FPSNode(name: \"tree_named\", fps: 7, then: NodeID(\"0x1\"),),]", &asset_server).unwrap();
+        assert_eq!(tree.names().node_name(ids[0]), Some("tree_named"));
+        assert_eq!(tree.names().attribute_name(Attribute::new_index("tree_named_index")), Some("tree_named_index"));
+        let mut other = AnimationNodeTree::<TestFlag>::default();
+        assert_eq!(other.names().node_name(ids[0]), None);
+        other.import_names(&tree.export_names()).unwrap();
+        assert_eq!(other.names().node_name(ids[0]), Some("tree_named"));
+        assert_eq!(ids[0].name_in(other.names()), Some("tree_named"));
+        assert_eq!(other.display(&BevySpriteAnimationError::NodeNotFound(ids[0])), "tree_named Not Found");
+        assert_eq!(ids[0].name_or_id(), format!("NodeID({:#018X})", ids[0].as_u64()));
+        let id = tree.load_node_from_str("NodeID(\"0x5EE5000000000002\"):FPSNode(name: \"tree_explicit\", fps: 7, then: NodeID(\"0x1\"),)", &asset_server).unwrap();
+        assert_eq!(tree.names().node_name(id), Some("tree_explicit"));
+        assert_eq!(id.name_in(tree.names()), Some("tree_explicit"));
+        assert_eq!(other.names().node_name(id), None);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn names_file_is_loaded() {
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        let missing = NodeID::from_u64(0x5EE5_0000_0000_0001);
+        assert_eq!(BevySpriteAnimationError::NodeNotFound(missing).to_string(), format!("NodeID({:#018X}) Not Found", missing.as_u64()));
+        tree.load_names_from_str(&format!("names_file_missing->{}", missing)).unwrap();
+        assert_eq!(tree.names().node_name(missing), Some("names_file_missing"));
+        assert_eq!(tree.display(&BevySpriteAnimationError::NodeNotFound(missing)), "names_file_missing Not Found");
+        // other trees and plain formatting do not see the names
+        assert_eq!(BevySpriteAnimationError::NodeNotFound(missing).to_string(), format!("NodeID({:#018X}) Not Found", missing.as_u64()));
+        let path = std::env::temp_dir().join("bevy_sprite_animation_names_file_is_loaded.names");
+        tree.save_names(&path).unwrap();
+        let mut other = AnimationNodeTree::<TestFlag>::default();
+        other.load_names(&path).unwrap();
+        assert_eq!(other.names().node_name(missing), Some("names_file_missing"));
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn start_node_from_str() {
+        assert_eq!("0x1".parse::<StartNode>().unwrap().0, NodeID::from_u64(1));
+        assert!(matches!("NodeID(0xZZ)".parse::<StartNode>(), Err(BevySpriteAnimationError::ParseError { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn strict_names_are_errors() {
+        let asset_server = test_asset_server();
+        let id = NodeID::from_name("strict_node");
+        let data = "FPSNode(name: \"strict_node\", fps: 7, then: NodeID(\"0x1\"),)";
+        let mut tree = AnimationNodeTree::<TestFlag>::default();
+        tree.names_mut().insert_node(id, "strict_other").unwrap();
+        tree.names_mut().set_strict(true);
+        assert!(matches!(tree.load_node_from_str(data, &asset_server), Err(BevySpriteAnimationError::NameCollision { .. })));
+        assert!(matches!(tree.load_tree_from_str(&format!("[{}]", data), &asset_server), Err(BevySpriteAnimationError::NameCollision { .. })));
+        assert!(matches!(tree.try_add_node(Box::new(FPSNode::new("strict_node", 7, id))), Err(BevySpriteAnimationError::NameCollision { .. })));
+        assert!(tree.get_node(id).is_none());
+        assert_eq!(tree.names().node_name(id), Some("strict_other"));
+        // without strict the collision is only logged and the first name is kept
+        tree.names_mut().set_strict(false);
+        assert_eq!(tree.load_node_from_str(data, &asset_server).unwrap(), id);
+        assert!(tree.get_node(id).is_some());
+        assert_eq!(tree.names().node_name(id), Some("strict_other"));
+        // try_add_node returns the collision either way
+        assert!(matches!(tree.try_add_node(Box::new(FPSNode::new("strict_node", 7, id))), Err(BevySpriteAnimationError::NameCollision { .. })));
+    }
 }
 
 pub struct SpriteAnimationPlugin<Flag>{
+    /// added to the tree with `AnimationNodeTree::register_attribute` when the plugin is built
+    #[cfg(feature = "ron")]
+    attribute_types: Vec<(Attribute, state::DataType)>,
     marker: PhantomData<Flag>
 }
 
 impl<F: 'static + Send + Sync> Default for SpriteAnimationPlugin<F> {
     fn default() -> SpriteAnimationPlugin<F>{
-        SpriteAnimationPlugin { marker: PhantomData::default() }
+        SpriteAnimationPlugin {
+            #[cfg(feature = "ron")]
+            attribute_types: Vec::new(),
+            marker: PhantomData::default()
+        }
+    }
+}
+
+impl<F> SpriteAnimationPlugin<F> {
+    /// registers `D` as the type of `attribute` on the tree, see `AnimationNodeTree::register_attribute`
+    #[cfg(feature = "ron")]
+    pub fn with_attribute<D: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static>(mut self, attribute: Attribute) -> Self {
+        self.attribute_types.push((attribute, state::DataType::of::<D>()));
+        self
     }
 }
 
 impl<F:'static + Send + Sync + Component> Plugin for SpriteAnimationPlugin<F> {
     fn build(&self, app: &mut App) {
-        app.insert_resource(AnimationNodeTree::<F>::default());
+        let mut tree = AnimationNodeTree::<F>::default();
+        #[cfg(feature = "ron")]
+        Arc::make_mut(&mut tree.attribute_types).extend(self.attribute_types.iter().copied());
+        app.insert_resource(tree);
+        app.add_event::<AnimationMarker>();
+        app.add_event::<ScriptEvent>();
+        app.add_event::<AnimationError>();
+        #[cfg(feature = "serialize")]
+        {
+            if !app.world.contains_resource::<Assets<NodeTreeAsset>>() {
+                app.add_asset::<NodeTreeAsset>();
+                app.init_asset_loader::<asset::NodeTreeAssetLoader>();
+            }
+            app.add_systems(Update, asset::reload_node_trees::<F>.in_set(AnimationSet::PreUpdate));
+            app.add_systems(Update, defaults::apply_attribute_defaults::<F>.after(asset::reload_node_trees::<F>).in_set(AnimationSet::PreUpdate));
+            app.add_systems(Update, sprite_sheet::add_pending_atlases::<F>.in_set(AnimationSet::PreUpdate));
+        }
         app.add_systems(Update, animation_system::<F>.in_set(AnimationSet::Update));
         app.add_systems(Update, state::update_delta::<F>.before(AnimationSet::Update).in_set(AnimationSet::PreUpdate));
         app.add_systems(First, state::clear_changed);
@@ -60,18 +285,26 @@ impl bevy_inspector_egui::Inspectable for StartNode {
             let mut name = self.0.name_or_id();
             ui.label("Start Node: ");
             if ui.text_edit_singleline(&mut name).changed() {
-                self.0 = NodeID::from_str(&name);
-                edit = true;
+                if let Ok(id) = name.parse() {
+                    self.0 = id;
+                    edit = true;
+                }
             }
         });
         edit
     }
 }
 
-impl StartNode {
-    pub fn from_str(name: &str) -> StartNode {
-        StartNode(NodeID::from_str(name))
+/// the start node for `NodeID(name)`, `NodeID(0x..)`, a number or a name, see `NodeID`'s `FromStr`
+impl std::str::FromStr for StartNode {
+    type Err = Error;
+
+    fn from_str(name: &str) -> Result<StartNode, Error> {
+        Ok(StartNode(name.parse()?))
     }
+}
+
+impl StartNode {
     pub fn from_u64(id: u64) -> StartNode {
         StartNode(NodeID::from_u64(id))
     }
@@ -80,11 +313,40 @@ impl StartNode {
     }
 }
 
+#[cfg(feature = "serialize")]
+type LoadedNode = (NodeID, Box<dyn AnimationNode>);
+
+/// the most nodes that are run for one entity each update unless changed with `AnimationNodeTree::set_max_depth`
+pub const DEFAULT_MAX_DEPTH: usize = 64;
+
 #[derive(Resource)]
 pub struct AnimationNodeTree<F> {
     nodes: HashMap<node_core::NodeID, Box<dyn node_core::AnimationNode>>,
+    max_depth: usize,
+    /// the `frame_count` of every node that has one, shared with each `AnimationState` for `frames_in` in scripts
+    frame_counts: Arc<HashMap<NodeID, usize>>,
+    /// the types of attributes registered with `register_attribute`, shared with each `AnimationState`
+    #[cfg(feature = "ron")]
+    attribute_types: Arc<HashMap<Attribute, state::DataType>>,
+    /// the names of the nodes and attributes this tree has loaded or been given, shared with `names::with_names` while formatting
+    names: Arc<NameRegistry>,
+    /// the `attributes:` sections of the loaded `.nodetree`s, set on each `AnimationState` run with this tree
+    #[cfg(feature = "serialize")]
+    attribute_defaults: HashMap<Attribute, defaults::AttributeDefault>,
+    /// changed each time `attribute_defaults` changes so states get them again
+    #[cfg(feature = "serialize")]
+    defaults_generation: u64,
+    /// run `validate` each time an asset finishes loading and warn about any problems
+    #[cfg(feature = "serialize")]
+    validate_on_load: bool,
     #[cfg(feature = "serialize")]
     loaders: HashMap<String, Box<dyn NodeLoader>>,
+    /// the nodes each loaded asset added to the tree; None until the asset has finished loading
+    #[cfg(feature = "serialize")]
+    sources: HashMap<Handle<NodeTreeAsset>, Option<Vec<NodeID>>>,
+    /// the sheets of inserted nodes whose atlases `sprite_sheet::add_pending_atlases` has not built yet
+    #[cfg(feature = "serialize")]
+    pending_atlases: Vec<SpriteSheet>,
     marker: PhantomData<F>,
 }
 
@@ -92,8 +354,23 @@ impl<F> Default for AnimationNodeTree<F> {
     fn default() -> AnimationNodeTree<F> {
         AnimationNodeTree {
             nodes: HashMap::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            frame_counts: Arc::default(),
+            #[cfg(feature = "ron")]
+            attribute_types: Arc::default(),
+            names: Arc::default(),
+            #[cfg(feature = "serialize")]
+            attribute_defaults: HashMap::new(),
+            #[cfg(feature = "serialize")]
+            defaults_generation: 0,
+            #[cfg(feature = "serialize")]
+            validate_on_load: false,
             #[cfg(feature = "serialize")]
             loaders: default_loaders(),
+            #[cfg(feature = "serialize")]
+            sources: HashMap::new(),
+            #[cfg(feature = "serialize")]
+            pending_atlases: Vec::new(),
             marker: PhantomData::default()
         }
     }
@@ -110,6 +387,95 @@ fn default_loaders() -> HashMap<String, Box<dyn NodeLoader>> {
 }
 
 impl<F> AnimationNodeTree<F> {
+    /// the most nodes that are run for one entity each update
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// sets the most nodes that are run for one entity each update;
+    /// an `AnimationError` is sent for any entity that needs more
+    pub fn set_max_depth(&mut self, max_depth: usize) {
+        self.max_depth = max_depth;
+    }
+
+    /// sets `D` as the type of `attribute` for every `AnimationState` run with this tree,
+    /// so ron can be read as it with `set_attribute_from_ron` or in a `ScriptNode` before the attribute is set in rust;
+    /// the type the attribute is set with on a state is used over this
+    #[cfg(feature = "ron")]
+    pub fn register_attribute<D: serde::Serialize + serde::de::DeserializeOwned + Send + Sync + 'static>(&mut self, attribute: Attribute) {
+        Arc::make_mut(&mut self.attribute_types).insert(attribute, state::DataType::of::<D>());
+    }
+
+    /// the starting values and temp flags set on each `AnimationState` from the `attributes:` sections of loaded `.nodetree`s
+    #[cfg(feature = "serialize")]
+    pub fn attribute_defaults(&self) -> &HashMap<Attribute, defaults::AttributeDefault> {
+        &self.attribute_defaults
+    }
+
+    /// adds a default like an entry of a `.nodetree` `attributes:` section; states already seen get it on the next update
+    #[cfg(feature = "serialize")]
+    pub fn set_attribute_default(&mut self, attribute: Attribute, default: defaults::AttributeDefault) {
+        self.attribute_defaults.insert(attribute, default);
+        self.defaults_generation += 1;
+    }
+
+    /// the names of the nodes and attributes this tree has loaded
+    pub fn names(&self) -> &NameRegistry {
+        &self.names
+    }
+
+    /// add names here for nodes and attributes made in rust so they are shown by name,
+    /// such as with `NodeID::try_from_name` or `TypedAttribute::register`
+    pub fn names_mut(&mut self) -> &mut NameRegistry {
+        Arc::make_mut(&mut self.names)
+    }
+
+    /// a copy of every name this tree knows, to be imported into another tree
+    pub fn export_names(&self) -> NameRegistry {
+        self.names.as_ref().clone()
+    }
+
+    /// adds every name in `names`;
+    /// names that collide with one already known are left out and the first collision is returned
+    pub fn import_names(&mut self, names: &NameRegistry) -> Result<(), Error> {
+        self.names_mut().import(names)
+    }
+
+    /// formats `value` with the names this tree knows, so ids, attributes, errors and reports are shown by name
+    pub fn display<T: std::fmt::Display + ?Sized>(&self, value: &T) -> String {
+        names::with_names(&self.names, || value.to_string())
+    }
+
+    /// adds the names in a `node.names` file, see `NameRegistry`'s `FromStr`
+    pub fn load_names_from_str(&mut self, data: &str) -> Result<(), Error> {
+        self.import_names(&data.parse()?)
+    }
+
+    /// adds the names in the `node.names` file at `path`
+    #[cfg(feature = "serialize")]
+    pub fn load_names<P: AsRef<std::path::Path>>(&mut self, path: P) -> Result<(), Error> {
+        self.load_names_from_str(&std::fs::read_to_string(path)?)
+    }
+
+    /// writes every node and attribute name this tree knows to `path` as a `node.names` file
+    #[cfg(feature = "serialize")]
+    pub fn save_names<P: AsRef<std::path::Path>>(&self, path: P) -> Result<(), Error> {
+        Ok(std::fs::write(path, self.names.to_string())?)
+    }
+
+    /// adds names found while loading and logs any collisions,
+    /// or returns the first collision and adds none of them if `NameRegistry::strict` is set
+    #[cfg(feature = "serialize")]
+    fn add_loaded_names(&mut self, loaded: Vec<names::Loaded>) -> Result<(), Error> {
+        if self.names.strict() {
+            self.names.check_loaded(&loaded)?;
+        }
+        for e in self.names_mut().insert_loaded(loaded) {
+            error!("{}", e);
+        }
+        Ok(())
+    }
+
     pub fn get_node(&self, id: NodeID) -> Option<&Box<dyn node_core::AnimationNode>> {
         self.nodes.get(&id)
     }
@@ -121,14 +487,69 @@ impl<F> AnimationNodeTree<F> {
         id
     }
 
+    /// adds `node` as its id, or returns the `NameCollision` of its name without adding it
     #[inline]
+    pub fn try_add_node(&mut self, node: Box<dyn node_core::AnimationNode>) -> Result<NodeID, Error> {
+        let id = node.id();
+        self.try_insert_node(id, node)?;
+        Ok(id)
+    }
+
+    /// adds `node` as `id`, or returns the `NameCollision` of its name with a different name that has `id` without adding it
+    pub fn try_insert_node(&mut self, id: NodeID, node: Box<dyn AnimationNode>) -> Result<(), Error> {
+        self.check_node_name(id, node.as_ref())?;
+        self.insert_node(id, node);
+        Ok(())
+    }
+
+    /// the `NameCollision` keeping the name of `node` for `id` would find
+    fn check_node_name(&self, id: NodeID, node: &dyn AnimationNode) -> Result<(), Error> {
+        if node.id() == id {
+            self.names.check_node(id, node.name())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// adds `node` as `id`; if its name collides with a different name that has `id`
+    /// the collision is logged and the first name is kept, use `try_insert_node` to get it instead
     pub fn insert_node(&mut self, id: NodeID, node: Box<dyn AnimationNode>) {
+        if node.id() == id {
+            if let Err(e) = self.names_mut().insert_node(id, node.name()) {
+                error!("{}", e);
+            }
+        } else {
+            // an id that is not the hash of the name is named after whichever node is put there
+            self.names_mut().rename_node(id, node.name());
+        }
+        let frame_counts = Arc::make_mut(&mut self.frame_counts);
+        match node.frame_count() {
+            Some(count) => frame_counts.insert(id, count),
+            None => frame_counts.remove(&id),
+        };
+        #[cfg(feature = "serialize")]
+        if let Some(sheet) = node.sprite_sheet() {
+            self.pending_atlases.push(sheet.clone());
+        }
         self.nodes.insert(id, node);
     }
 
+    /// the tree as a `.nodetree`, with attributes written by the names this tree knows
     #[cfg(feature = "serialize")]
     pub fn serialize(&self, asset_server: &AssetServer) -> Result<String, Error> {
+        names::with_names(&self.names, || self.write_tree(asset_server))
+    }
+
+    #[cfg(feature = "serialize")]
+    fn write_tree(&self, asset_server: &AssetServer) -> Result<String, Error> {
         let mut data = String::new();
+        if !self.attribute_defaults.is_empty() {
+            data.push_str("attributes: {\n");
+            for (attribute, default) in self.attribute_defaults.iter() {
+                data.push_str(&format!("\t{}: {},\n", attribute, default));
+            }
+            data.push_str("},\n");
+        }
         data.push('[');
         data.push('\n');
         for (id, node) in self.nodes.iter() {
@@ -158,102 +579,111 @@ impl<F> AnimationNodeTree<F> {
 
     }
 
+    /// Starts loading a `.node` or `.nodetree` file as a `NodeTreeAsset`
+    /// the nodes are added to the tree once the asset has loaded
+    /// and are replaced whenever the asset is reloaded
     #[cfg(feature = "serialize")]
-    pub fn load<P: AsRef<std::path::Path>>(&mut self, path: P, asset_server: &AssetServer) -> Result<(), Error>{
-        let io = asset_server.asset_io().load_path(path.as_ref());
-        
-        let tree = if let Some(ext) = path.as_ref().extension() {
-            let t = ext == "nodetree";
-            if !(ext == "node" || t) {
-                return Err(Error::InvalidExtension(ext.to_str().unwrap().to_string()));
-            }
-            t
-        } else {false};
-        let data = String::from_utf8(futures_lite::future::block_on(io)?)?;
+    pub fn load<P: AsRef<std::path::Path>>(&mut self, path: P, asset_server: &AssetServer) -> Result<Handle<NodeTreeAsset>, Error>{
+        match path.as_ref().extension() {
+            Some(ext) if ext == "node" || ext == "nodetree" => {},
+            Some(ext) => return Err(Error::InvalidExtension(ext.to_string_lossy().to_string())),
+            None => return Err(Error::InvalidExtension(String::new())),
+        }
+        let handle: Handle<NodeTreeAsset> = asset_server.load(path.as_ref());
+        self.sources.entry(handle.clone()).or_insert(None);
+        Ok(handle)
+    }
 
-        if tree {
-            info!("loaded {:?} from {:?}",self.load_tree_from_str(&data, asset_server)?, path.as_ref());
+    /// Builds the nodes in `asset` and replaces any nodes that were previously loaded from `handle`
+    /// if the asset fails to load the old nodes are kept
+    /// old nodes whose id another asset has loaded since are left to that asset
+    #[cfg(feature = "serialize")]
+    pub fn load_asset(&mut self, handle: &Handle<NodeTreeAsset>, asset: &NodeTreeAsset, asset_server: &AssetServer) -> Result<Vec<NodeID>, Error> {
+        let nodes = if asset.is_tree() {
+            self.parse_tree(asset.data(), asset_server)?
         } else {
-            info!("loaded {} from {:?}",self.load_node_from_str(&data, asset_server)?, path.as_ref());
+            vec![self.load_node(asset.data(), asset_server)?]
+        };
+        // every node is checked before the tree changes so a failed load leaves it as it was
+        if self.names.strict() {
+            for (id, node) in nodes.iter() {
+                self.check_node_name(*id, node.as_ref())?;
+            }
         }
-        Ok(())
+        if let Some(Some(old)) = self.sources.get(handle) {
+            let owned: Vec<NodeID> = old.iter().filter(|id| !self.sources.iter().any(|(other, ids)| {
+                other != handle && ids.as_ref().is_some_and(|ids| ids.contains(id))
+            })).copied().collect();
+            let frame_counts = Arc::make_mut(&mut self.frame_counts);
+            for id in owned.iter() {
+                self.nodes.remove(id);
+                frame_counts.remove(id);
+            }
+        }
+        let ids: Vec<NodeID> = nodes.iter().map(|(id, _)| *id).collect();
+        for (id, node) in nodes.into_iter() {
+            self.insert_node(id, node);
+        }
+        *self.sources.entry(handle.clone_weak()).or_default() = Some(ids.clone());
+        Ok(ids)
+    }
+
+    /// when set the tree is validated with the start nodes of every entity using it
+    /// once all the assets passed to `load` have loaded or reloaded, and any problems are logged as warnings
+    #[cfg(feature = "serialize")]
+    pub fn set_validate_on_load(&mut self, validate: bool) {
+        self.validate_on_load = validate;
+    }
+
+    /// true if `handle` was loaded with `load`
+    #[cfg(feature = "serialize")]
+    pub fn has_source(&self, handle: &Handle<NodeTreeAsset>) -> bool {
+        self.sources.contains_key(handle)
+    }
+
+    /// true while any asset passed to `load` has not finished loading
+    #[cfg(feature = "serialize")]
+    pub fn is_loading(&self) -> bool {
+        self.sources.values().any(|ids| ids.is_none())
     }
 
     #[cfg(feature = "serialize")]
     pub fn load_node_from_str(&mut self, data: &str, asset_server: &AssetServer) -> Result<NodeID, Error> {
         let (id, node) = self.load_node(data, asset_server)?;
+        if self.names.strict() {
+            self.check_node_name(id, node.as_ref())?;
+        }
         self.insert_node(id, node);
         Ok(id)
     }
 
     #[cfg(feature = "serialize")]
     pub fn load_node(&mut self, data: &str, asset_server: &AssetServer) -> Result<(NodeID, Box<dyn AnimationNode>), Error> {
-        let data: &str = data.trim();
+        let (node, loaded) = names::collect(|| self.read_node(data, asset_server));
+        self.add_loaded_names(loaded)?;
+        node
+    }
 
-        let node_id: Option<NodeID> = if data.trim().starts_with("NodeID(\"") {
-            let end = data.find(')').ok_or(Error::MalformedStr { message: format!("Failed to find ')' "), location: here!() })? + 1;
-            //info!("data = {}", &data[..end]);
-            Some(ron::from_str(&data[..end])?)
-        } else {
-            None
+    #[cfg(feature = "serialize")]
+    fn read_node(&mut self, data: &str, asset_server: &AssetServer) -> Result<(NodeID, Box<dyn AnimationNode>), Error> {
+        let (node_id, kind, data) = node_parts(data)?;
+        let node_id: Option<NodeID> = match node_id {
+            Some(node_id) => Some(ron::from_str(node_id)?),
+            None => None,
         };
-
-        let loader = if node_id.is_some() {data.find(':').ok_or(Error::MalformedStr{
-            message: format!("Failed to find NodeID : Node seperator"),
-            location: here!()
-        })? + 1} else {0};
-
-        let start: usize = if let Some(i) = data[loader..].find('(') {i + loader} else { return Err(Error::MalformedStr{
-            message: format!("Failed to Find Oppening ( in str"),
-            location: here!(),
-        }
-        )};
-        let loader = self.loaders.get_mut(&data[loader..start]).ok_or(Error::NoLoader(data[loader..start].to_string()))?;
-        let node = loader.load(&data[start..], asset_server)?;
+        let loader = self.loaders.get_mut(kind).ok_or(Error::NoLoader(kind.to_string()))?;
+        let node = loader.load(data, asset_server)?;
         let node_id = if node_id.is_some() {node_id.unwrap()} else {node.id()};
         Ok((node_id, node))
     }
 
     #[cfg(feature = "serialize")]
     pub fn load_tree_from_str(&mut self, data: &str, asset_server: &AssetServer) -> Result<Vec<NodeID>, Error> {
-        let data = data.trim();
-        let data = if data.starts_with('[') {data[1..].trim()} else {data};
-        let mut nodes = Vec::new();
-        let mut index = 0;
-        loop {
-        if index >= data.len() {
-            break;
-        }
-        let data = &data[index..];
-        if let Some(next) = data.chars().next() {
-            if next.is_whitespace() || next == ',' || next == ']' || next == ')' || next == '}' {
-                index += 1;
-                trace!("skiped {} at begging of node?\n{}", next, here!());
-                continue;
-            }
-        };
-
-        let start = if data.trim().starts_with("NodeID(\"") {
-            data.find("NodeID(\"").unwrap() + 30
-        } else {0};
-
-        let mut open = data[start..].match_indices('(');
-        open.next();
-        let mut close = data[start..].match_indices(')');
-        let end = loop {
-            match (open.next(), close.next()) {
-                (_, None) => return Err(
-                                Error::MalformedStr{
-                                    message: format!("Failed to find ) "),
-                                    location: here!()
-                                }),
-                (None, Some((end,_))) => {break end + start;},
-                (Some((open,_)), Some((close,_))) => if close < open {break close + start;}
+        let nodes = self.parse_tree(data, asset_server)?;
+        if self.names.strict() {
+            for (id, node) in nodes.iter() {
+                self.check_node_name(*id, node.as_ref())?;
             }
-        };
-        let data = &data[..end + 1];
-        nodes.push(self.load_node(data, asset_server)?);
-        index += end + 1;
         }
         let mut ids = Vec::new();
         for (id, node) in nodes.into_iter() {
@@ -262,27 +692,180 @@ impl<F> AnimationNodeTree<F> {
         }
         Ok(ids)
     }
+
+    #[cfg(feature = "serialize")]
+    fn parse_tree(&mut self, data: &str, asset_server: &AssetServer) -> Result<Vec<LoadedNode>, Error> {
+        let (nodes, loaded) = names::collect(|| self.read_tree(data, asset_server));
+        self.add_loaded_names(loaded)?;
+        nodes
+    }
+
+    #[cfg(feature = "serialize")]
+    fn read_tree(&mut self, data: &str, asset_server: &AssetServer) -> Result<Vec<LoadedNode>, Error> {
+        let data = match defaults::parse_attributes(data)? {
+            Some((entries, rest)) => {
+                for (attribute, default) in entries {
+                    self.set_attribute_default(attribute, default);
+                }
+                rest
+            },
+            None => data,
+        };
+        let mut nodes = Vec::new();
+        for data in split_nodes(data)? {
+            nodes.push(self.read_node(data, asset_server)?);
+        }
+        Ok(nodes)
+    }
+}
+
+/// splits a list of nodes, `[Node(..), ..]`, into the str of each node
+#[cfg(feature = "serialize")]
+pub(crate) fn split_nodes(data: &str) -> Result<Vec<&str>, Error> {
+    let data = data.trim();
+    let data = if data.starts_with('[') {data[1..].trim()} else {data};
+    let mut nodes = Vec::new();
+    let mut index = 0;
+    loop {
+    if index >= data.len() {
+        break;
+    }
+    let data = &data[index..];
+    if let Some(next) = data.chars().next() {
+        if next.is_whitespace() || next == ',' || next == ']' || next == ')' || next == '}' {
+            index += 1;
+            trace!("skiped {} at begging of node?\n{}", next, here!());
+            continue;
+        }
+    };
+
+    let start = if data.trim().starts_with("NodeID(\"") {
+        data.find("NodeID(\"").unwrap() + 30
+    } else {0};
+
+    let Some(node) = data.get(start..) else {
+        return Err(Error::MalformedStr { message: format!("expected a node after the NodeID in `{}`", data), location: here!() });
+    };
+    let mut open = node.match_indices('(');
+    open.next();
+    let mut close = node.match_indices(')');
+    let end = loop {
+        match (open.next(), close.next()) {
+            (_, None) => return Err(
+                            Error::MalformedStr{
+                                message: format!("Failed to find ) "),
+                                location: here!()
+                            }),
+            (None, Some((end,_))) => {break end + start;},
+            (Some((open,_)), Some((close,_))) => if close < open {break close + start;}
+        }
+    };
+    let data = &data[..end + 1];
+    nodes.push(data);
+    index += end + 1;
+    }
+    Ok(nodes)
+}
+
+/// splits a node into the str of its `NodeID` if it starts with one, the name of its loader and its fields
+#[cfg(feature = "serialize")]
+pub(crate) fn node_parts(data: &str) -> Result<(Option<&str>, &str, &str), Error> {
+    let data: &str = data.trim();
+
+    let node_id = if data.starts_with("NodeID(\"") {
+        let end = data.find(')').ok_or(Error::MalformedStr { message: format!("Failed to find ')' "), location: here!() })? + 1;
+        Some(&data[..end])
+    } else {
+        None
+    };
+
+    let loader = if node_id.is_some() {data.find(':').ok_or(Error::MalformedStr{
+        message: format!("Failed to find NodeID : Node seperator"),
+        location: here!()
+    })? + 1} else {0};
+
+    let start: usize = if let Some(i) = data[loader..].find('(') {i + loader} else { return Err(Error::MalformedStr{
+        message: format!("Failed to Find Oppening ( in str"),
+        location: here!(),
+    }
+    )};
+    Ok((node_id, data[loader..start].trim(), &data[start..]))
 }
 
+/// the components a `Frame` can be written to
+type FrameTarget<'a> = (Option<&'a mut Handle<Image>>, Option<(&'a mut Handle<TextureAtlas>, &'a mut TextureAtlasSprite)>);
+
 fn animation_system<Flag: Component>(
     nodes: Res<AnimationNodeTree<Flag>>,
-    mut query: Query<(&mut state::AnimationState, &mut Handle<Image>, &StartNode), With<Flag>>
+    mut query: Query<(Entity, &mut state::AnimationState, &StartNode, FrameTarget), With<Flag>>,
+    mut markers: EventWriter<AnimationMarker>,
+    mut script_events: EventWriter<ScriptEvent>,
+    mut errors: EventWriter<AnimationError>,
 ){
-    for (mut state,mut handle, start) in query.iter_mut() {
-        let mut next = NodeResult::Next(start.0.clone());
+    for (entity, mut state, start, (image, atlas)) in query.iter_mut() {
+        if !Arc::ptr_eq(&state.frame_counts, &nodes.frame_counts) {
+            state.frame_counts = nodes.frame_counts.clone();
+        }
+        #[cfg(feature = "ron")]
+        if !Arc::ptr_eq(&state.attribute_types, &nodes.attribute_types) {
+            state.attribute_types = nodes.attribute_types.clone();
+        }
+        if state.seed.is_none() {
+            state.set_seed(entity.to_bits());
+        }
+        let mut next = NodeResult::Next(start.0);
+        let mut path: Vec<NodeID> = Vec::new();
         trace!("Starting With: {}",start.0);
-        loop {
+        let error = loop {
             match next {
-                NodeResult::Next(id) => {if let Some(node) = nodes.get_node(id) {
-                    trace!("Running Node: {}",id);
-                    next = node.run(&mut state);
-                } else {
-                    error!("Node not found: {}",id);
-                    break;
-                }},
-                NodeResult::Error(e) => {error!("{}",e); break;}
-                NodeResult::Done(h) => {*handle = h; break;},
+                NodeResult::Next(id) => {
+                    if let Some(first) = path.iter().position(|visited| *visited == id) {
+                        let mut cycle = path.split_off(first);
+                        cycle.push(id);
+                        break Some(AnimationErrorKind::Cycle(cycle));
+                    }
+                    if path.len() >= nodes.max_depth {
+                        break Some(AnimationErrorKind::MaxDepth(path));
+                    }
+                    path.push(id);
+                    if let Some(node) = nodes.get_node(id) {
+                        trace!("Running Node: {}",id);
+                        next = node.run(&mut state);
+                        for (marker, frame) in state.markers.drain(..) {
+                            markers.send(AnimationMarker { entity, node: id, marker, frame });
+                        }
+                        for (name, payload) in state.events.drain(..) {
+                            script_events.send(ScriptEvent { entity, node: id, name, payload });
+                        }
+                    } else {
+                        #[cfg(feature = "serialize")]
+                        if nodes.is_loading() {
+                            break None;
+                        }
+                        break Some(AnimationErrorKind::NodeNotFound(id));
+                    }
+                },
+                NodeResult::Error(e) => {
+                    let id = path.pop().unwrap_or(start.0);
+                    break Some(AnimationErrorKind::NodeError(id, e));
+                }
+                NodeResult::Done(frame) => {
+                    match (frame, image, atlas) {
+                        (Frame::Image(h), Some(mut handle), _) => *handle = h,
+                        (Frame::Atlas(h, index), _, Some((mut handle, mut sprite))) => {
+                            *handle = h;
+                            sprite.index = index;
+                        },
+                        (Frame::Image(_), None, _) => error!("Image frame returned for an entity without a Handle<Image>"),
+                        (Frame::Atlas(..), _, None) => error!("Atlas frame returned for an entity without a Handle<TextureAtlas> and TextureAtlasSprite"),
+                    }
+                    break None;
+                },
             }
+        };
+        if let Some(kind) = error {
+            error!("{:?}: {}", entity, nodes.display(&kind));
+            errors.send(AnimationError { entity, kind });
         }
     }
-}
\ No newline at end of file
+}
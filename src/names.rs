@@ -0,0 +1,414 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::error::BevySpriteAnimationError as Error;
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn index_collision_is_found() {
+        let mut seen = HashMap::new();
+        let (first, second) = (0..).find_map(|i| {
+            let name = format!("collision_{}", i);
+            let id = Attribute::hash_for_index(&name);
+            seen.insert(id, name.clone()).map(|first| (first, name))
+        }).unwrap();
+        let mut names = NameRegistry::default();
+        let att = names.attribute_index(&first).unwrap();
+        assert_eq!(Attribute::try_new_index(&first, &mut names).unwrap(), att);
+        assert!(matches!(names.attribute_index(&second), Err(Error::NameCollision { .. })));
+        assert!(matches!(Attribute::try_new_index(&second, &mut names), Err(Error::NameCollision { .. })));
+        assert!(matches!(names.insert_attribute(att, &second), Err(Error::NameCollision { .. })));
+        // the first name is kept
+        assert_eq!(names.attribute_name(att), Some(first.as_str()));
+    }
+
+    #[test]
+    fn node_collision_is_found() {
+        let mut names = NameRegistry::default();
+        let id = NodeID::try_from_name("collision_node", &mut names).unwrap();
+        assert!(matches!(names.insert_node(id, "collision_other"), Err(Error::NameCollision { .. })));
+        assert_eq!(names.node_name(id), Some("collision_node"));
+        names.rename_node(id, "collision_other");
+        assert_eq!(names.node_name(id), Some("collision_other"));
+    }
+
+    #[test]
+    fn names_are_collected() {
+        let (id, loaded) = collect(|| {
+            Attribute::new_index("collect_index");
+            "Attribute(collect_attribute)".parse::<Attribute>().unwrap();
+            NodeID::from_name("collect_node")
+        });
+        let mut names = NameRegistry::default();
+        assert!(names.insert_loaded(loaded).is_empty());
+        assert_eq!(names.node_name(id), Some("collect_node"));
+        assert_eq!(names.attribute_name(Attribute::new_index("collect_index")), Some("collect_index"));
+        assert_eq!(names.attribute_name(Attribute::new_attribute("collect_attribute")), Some("collect_attribute"));
+        // nothing is kept outside of `collect`
+        let (_, empty) = collect(|| ());
+        assert!(empty.is_empty());
+        assert_eq!(NameRegistry::default().attribute_name(Attribute::new_index("collect_index")), None);
+        // the same name hashed twice is not a collision
+        let (_, loaded) = collect(|| {
+            NodeID::from_name("collect_same");
+            NodeID::from_name("collect_same");
+        });
+        assert!(NameRegistry::default().insert_loaded(loaded).is_empty());
+        // a loader that panics does not leave the names collecting
+        assert!(std::panic::catch_unwind(|| collect(|| panic!("collect_panic"))).is_err());
+        assert!(COLLECTING.with(|loaded| loaded.borrow().is_none()));
+        let (_, outer) = collect(|| {
+            let _ = std::panic::catch_unwind(|| collect(|| panic!("collect_panic")));
+            NodeID::from_name("collect_outer");
+        });
+        assert_eq!(outer.len(), 1);
+    }
+
+    #[test]
+    fn names_are_shown_in_scope() {
+        let mut names = NameRegistry::default();
+        let stand = names.attribute_index("scope_stand").unwrap();
+        let id = names.node_id("scope_node").unwrap();
+        let names = Arc::new(names);
+        assert_eq!(stand.to_string(), format!("Index({:#06X})", stand.as_u64()));
+        assert_eq!(with_names(&names, || (stand.to_string(), id.name_or_id())), ("Index(scope_stand)".to_string(), "scope_node".to_string()));
+        assert_eq!(id.name_or_id(), format!("NodeID({:#018X})", id.as_u64()));
+        assert_eq!(id.name_in(&names), Some("scope_node"));
+        assert_eq!(Attribute::DELTA.to_string(), "Core(Delta)");
+    }
+
+    #[test]
+    fn import_keeps_first_name() {
+        let mut names = NameRegistry::default();
+        names.node_id("import_a").unwrap();
+        let mut other = NameRegistry::default();
+        other.insert_node(NodeID::from_name("import_a"), "import_b").unwrap();
+        other.node_id("import_c").unwrap();
+        assert!(matches!(names.import(&other), Err(Error::NameCollision { .. })));
+        assert_eq!(names.node_name(NodeID::from_name("import_a")), Some("import_a"));
+        assert_eq!(names.node_name(NodeID::from_name("import_c")), Some("import_c"));
+    }
+
+    #[test]
+    fn names_file_round_trip() {
+        let mut names = NameRegistry::default();
+        names.node_id("file_node").unwrap();
+        names.attribute_index("file_index").unwrap();
+        names.attribute("file_attribute").unwrap();
+        let data = names.to_string();
+        assert!(data.contains(&format!("file_node->{}\n", NodeID::from_name("file_node"))));
+        assert!(!data.contains("FlipX"));
+        let read: NameRegistry = data.parse().unwrap();
+        assert_eq!(read.node_name(NodeID::from_name("file_node")), Some("file_node"));
+        assert_eq!(read.attribute_name(Attribute::new_index("file_index")), Some("file_index"));
+        assert_eq!(read.attribute_name(Attribute::new_attribute("file_attribute")), Some("file_attribute"));
+        assert_eq!(read.to_string(), data);
+    }
+
+    #[test]
+    fn names_file_lines() {
+        let read: NameRegistry = "\nZombie8_SwitchNode->NodeID(0x0051261A24EE838A02)\n  Zombie8_Idle -> NodeID(0x00D7C1AE2EA61749B8)\n".parse().unwrap();
+        assert_eq!(NodeID::from_name("Zombie8_Idle"), NodeID::from_u64(0xD7C1AE2EA61749B8));
+        assert_eq!(read.node_name(NodeID::from_name("Zombie8_SwitchNode")), Some("Zombie8_SwitchNode"));
+        assert_eq!(read.node_name(NodeID::from_u64(0xD7C1AE2EA61749B8)), Some("Zombie8_Idle"));
+        assert!(matches!("Zombie8_Idle NodeID(0x1)".parse::<NameRegistry>(), Err(Error::ParseError { .. })));
+        assert!(matches!("->NodeID(0x1)".parse::<NameRegistry>(), Err(Error::ParseError { .. })));
+        assert!(matches!("a->Index(0x1)".parse::<NameRegistry>(), Err(Error::ParseError { .. })));
+        assert!(matches!("a->NodeID(0x1)\nb->NodeID(0x1)".parse::<NameRegistry>(), Err(Error::NameCollision { .. })));
+    }
+}
+
+/// The names of `NodeID`s and `Attribute`s, kept by each `AnimationNodeTree` for the names it has loaded
+/// ids are hashes of their names so the names are only needed to show and save them
+#[derive(Debug, Clone)]
+pub struct NameRegistry {
+    nodes: HashMap<NodeID, String>,
+    attributes: HashMap<Attribute, String>,
+    /// return collisions found while loading as errors instead of logging them
+    strict: bool,
+}
+
+impl Default for NameRegistry {
+    fn default() -> Self {
+        let mut attributes = HashMap::new();
+        attributes.insert(Attribute::NULL,          "null".to_string());
+        attributes.insert(Attribute::DELTA,         "Delta".to_string());
+        attributes.insert(Attribute::TIME_ON_FRAME, "FrameTime".to_string());
+        attributes.insert(Attribute::FRAMES,        "Frames".to_string());
+        attributes.insert(Attribute::FLIP_X,        "FlipX".to_string());
+        attributes.insert(Attribute::FLIP_Y,        "FlipY".to_string());
+        attributes.insert(Attribute::LAST_FPS,      "LAST_FPS".to_string());
+        NameRegistry { nodes: HashMap::new(), attributes, strict: false }
+    }
+}
+
+impl NameRegistry {
+    /// the id of `name`, adding it as that id's name
+    /// or `NameCollision` if a different name already has the id
+    pub fn node_id(&mut self, name: &str) -> Result<NodeID, Error> {
+        let id = NodeID::from_name(name);
+        self.insert_node(id, name)?;
+        Ok(id)
+    }
+
+    /// the index for `name`, adding it as that index's name
+    /// or `NameCollision` if a different name already has the id
+    pub fn attribute_index(&mut self, name: &str) -> Result<Attribute, Error> {
+        let att = Attribute::new_index(name);
+        self.insert_attribute(att, name)?;
+        Ok(att)
+    }
+
+    /// the custom attribute for `name`, adding it as that attribute's name
+    /// or `NameCollision` if a different name already has the id
+    pub fn attribute(&mut self, name: &str) -> Result<Attribute, Error> {
+        let att = Attribute::new_attribute(name);
+        self.insert_attribute(att, name)?;
+        Ok(att)
+    }
+
+    /// sets the name of `id`, which does not need to be the hash of `name`
+    /// if a different name already has `id` it is kept and a `NameCollision` is returned
+    pub fn insert_node(&mut self, id: NodeID, name: &str) -> Result<(), Error> {
+        insert(&mut self.nodes, id, id.as_u64(), name)
+    }
+
+    /// the `NameCollision` `insert_node` would return, without adding the name
+    pub(crate) fn check_node(&self, id: NodeID, name: &str) -> Result<(), Error> {
+        check(&self.nodes, &id, id.as_u64(), name)
+    }
+
+    /// sets the name of `id`, replacing any name it had
+    pub fn rename_node(&mut self, id: NodeID, name: &str) {
+        self.nodes.insert(id, name.trim().to_string());
+    }
+
+    /// sets the name of `attribute`
+    /// if a different name already has `attribute` it is kept and a `NameCollision` is returned
+    pub fn insert_attribute(&mut self, attribute: Attribute, name: &str) -> Result<(), Error> {
+        insert(&mut self.attributes, attribute, attribute.as_u64(), name)
+    }
+
+    /// when set an `AnimationNodeTree` returns a `NameCollision` from loading a node
+    /// whose name collides with a different name instead of logging it
+    pub fn set_strict(&mut self, strict: bool) {
+        self.strict = strict;
+    }
+
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    pub fn node_name(&self, id: NodeID) -> Option<&str> {
+        self.nodes.get(&id).map(String::as_str)
+    }
+
+    pub fn attribute_name(&self, attribute: Attribute) -> Option<&str> {
+        self.attributes.get(&attribute).map(String::as_str)
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeID, &str)> {
+        self.nodes.iter().map(|(id, name)| (*id, name.as_str()))
+    }
+
+    /// every named attribute, including the core attributes
+    pub fn attributes(&self) -> impl Iterator<Item = (Attribute, &str)> {
+        self.attributes.iter().map(|(att, name)| (*att, name.as_str()))
+    }
+
+    /// adds every name in `other`
+    /// names that collide with one already here are left out and the first collision is returned
+    pub fn import(&mut self, other: &NameRegistry) -> Result<(), Error> {
+        let mut res = Ok(());
+        for (id, name) in other.nodes() {
+            if let (Err(e), Ok(())) = (self.insert_node(id, name), &res) {
+                res = Err(e);
+            }
+        }
+        for (att, name) in other.attributes() {
+            if let (Err(e), Ok(())) = (self.insert_attribute(att, name), &res) {
+                res = Err(e);
+            }
+        }
+        res
+    }
+
+    /// the first `NameCollision` `insert_loaded` would find, with these names or between the loaded names
+    pub(crate) fn check_loaded(&self, loaded: &[Loaded]) -> Result<(), Error> {
+        let mut nodes = HashMap::new();
+        let mut attributes = HashMap::new();
+        for loaded in loaded {
+            match loaded {
+                Loaded::Node(id, name) => {
+                    check(&self.nodes, id, id.as_u64(), name)?;
+                    insert(&mut nodes, *id, id.as_u64(), name)?;
+                },
+                Loaded::Attribute(att, name) => {
+                    check(&self.attributes, att, att.as_u64(), name)?;
+                    insert(&mut attributes, *att, att.as_u64(), name)?;
+                },
+            }
+        }
+        Ok(())
+    }
+
+    /// adds the names collected while loading, returning every collision
+    pub(crate) fn insert_loaded(&mut self, loaded: Vec<Loaded>) -> Vec<Error> {
+        loaded.into_iter().filter_map(|loaded| match loaded {
+            Loaded::Node(id, name) => self.insert_node(id, &name).err(),
+            Loaded::Attribute(att, name) => self.insert_attribute(att, &name).err(),
+        }).collect()
+    }
+}
+
+/// the `node.names` format, one `name->NodeID(0x..)`, `name->Index(0x..)` or `name->Attribute(0x..)` line per name;
+/// the core attributes are left out as every registry has them
+impl std::fmt::Display for NameRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut nodes: Vec<_> = self.nodes().collect();
+        nodes.sort_by(|a, b| a.1.cmp(b.1).then(a.0.as_u64().cmp(&b.0.as_u64())));
+        for (id, name) in nodes {
+            writeln!(f, "{}->{}", name, id)?;
+        }
+        let mut attributes: Vec<_> = self.attributes().filter(|(att, _)| !att.is_core()).collect();
+        attributes.sort_by(|a, b| a.1.cmp(b.1).then(a.0.as_u64().cmp(&b.0.as_u64())));
+        for (att, name) in attributes {
+            if att.is_index() {
+                writeln!(f, "{}->Index({:#06X})", name, att.as_u64())?;
+            } else {
+                writeln!(f, "{}->Attribute({:#018X})", name, att.as_u64())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// reads the `node.names` format, blank lines are skipped;
+/// a name that collides with an earlier line is a `NameCollision`
+impl std::str::FromStr for NameRegistry {
+    type Err = Error;
+
+    fn from_str(data: &str) -> Result<Self, Self::Err> {
+        let mut names = NameRegistry::default();
+        for line in data.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            let error = |message: &str| Error::ParseError { kind: "names line", input: line.to_string(), message: message.to_string() };
+            let (name, id) = line.rsplit_once("->").ok_or_else(|| error("expected `name->id`"))?;
+            let (name, id) = (name.trim(), id.trim());
+            if name.is_empty() {
+                return Err(error("the name is empty"));
+            }
+            if id.starts_with("NodeID") {
+                names.insert_node(id.parse().map_err(|e: Error| error(&e.to_string()))?, name)?;
+            } else {
+                let att: Attribute = id.parse().map_err(|e: Error| error(&e.to_string()))?;
+                if !att.is_core() {
+                    names.insert_attribute(att, name)?;
+                }
+            }
+        }
+        Ok(names)
+    }
+}
+
+/// the `NameCollision` adding `name` to `names` as `id` would return
+fn check<K: std::hash::Hash + Eq>(names: &HashMap<K, String>, id: &K, raw_id: u64, name: &str) -> Result<(), Error> {
+    let name = name.trim();
+    match names.get(id) {
+        Some(existing) if existing != name => Err(Error::NameCollision {
+            name: name.to_string(),
+            existing: existing.clone(),
+            id: raw_id,
+        }),
+        _ => Ok(()),
+    }
+}
+
+/// adds `name` to `names` as `id`
+/// if a different name already has `id` it is left and a `NameCollision` is returned
+fn insert<K: std::hash::Hash + Eq>(names: &mut HashMap<K, String>, id: K, raw_id: u64, name: &str) -> Result<(), Error> {
+    check(names, &id, raw_id, name)?;
+    names.entry(id).or_insert_with(|| name.trim().to_string());
+    Ok(())
+}
+
+/// a name hashed while a tree was loading, see `collect`
+pub(crate) enum Loaded {
+    Node(NodeID, String),
+    Attribute(Attribute, String),
+}
+
+thread_local! {
+    /// the names hashed on this thread while `collect` is running
+    static COLLECTING: RefCell<Option<Vec<Loaded>>> = const { RefCell::new(None) };
+    /// the names of the tree formatting on this thread, see `with_names`
+    static SHOWING: RefCell<Option<Arc<NameRegistry>>> = const { RefCell::new(None) };
+}
+
+/// puts the names that were being collected before `collect` back, even if it panics
+struct Collecting(Option<Vec<Loaded>>);
+
+impl Drop for Collecting {
+    fn drop(&mut self) {
+        let outer = self.0.take();
+        COLLECTING.with(|loaded| loaded.replace(outer));
+    }
+}
+
+/// runs `f` and returns the names hashed with `NodeID::from_name`, `Attribute::new_index`
+/// and `Attribute::new_attribute` on this thread while it ran
+/// used by `AnimationNodeTree` to find the names of the nodes and attributes it loads,
+/// as they are hashed inside node loaders and `Deserialize` impls that are not given the tree
+pub(crate) fn collect<T>(f: impl FnOnce() -> T) -> (T, Vec<Loaded>) {
+    let _outer = Collecting(COLLECTING.with(|loaded| loaded.replace(Some(Vec::new()))));
+    let res = f();
+    let loaded = COLLECTING.with(|loaded| loaded.take()).unwrap_or_default();
+    (res, loaded)
+}
+
+/// adds a node name to the names being collected, if any
+pub(crate) fn record_node(id: NodeID, name: &str) {
+    COLLECTING.with(|loaded| {
+        if let Some(loaded) = loaded.borrow_mut().as_mut() {
+            loaded.push(Loaded::Node(id, name.to_string()));
+        }
+    });
+}
+
+/// adds an attribute name to the names being collected, if any
+pub(crate) fn record_attribute(attribute: Attribute, name: &str) {
+    COLLECTING.with(|loaded| {
+        if let Some(loaded) = loaded.borrow_mut().as_mut() {
+            loaded.push(Loaded::Attribute(attribute, name.to_string()));
+        }
+    });
+}
+
+/// puts the names that were shown before `with_names` back, even if it panics
+struct Showing(Option<Arc<NameRegistry>>);
+
+impl Drop for Showing {
+    fn drop(&mut self) {
+        let outer = self.0.take();
+        SHOWING.with(|names| names.replace(outer));
+    }
+}
+
+/// runs `f` with `names` used by `NodeID::name_or_id` and `Attribute`'s `Display` on this thread
+/// used by `AnimationNodeTree::display` and when a tree serializes or logs
+pub(crate) fn with_names<T>(names: &Arc<NameRegistry>, f: impl FnOnce() -> T) -> T {
+    let _outer = Showing(SHOWING.with(|showing| showing.replace(Some(names.clone()))));
+    f()
+}
+
+pub(crate) fn shown_node_name(id: NodeID) -> Option<String> {
+    SHOWING.with(|names| names.borrow().as_ref().and_then(|names| names.node_name(id).map(str::to_string)))
+}
+
+pub(crate) fn shown_attribute_name(attribute: Attribute) -> Option<String> {
+    SHOWING.with(|names| names.borrow().as_ref().and_then(|names| names.attribute_name(attribute).map(str::to_string)))
+}
@@ -1,6 +1,29 @@
 use bevy::prelude::*;
 use crate::error::BevySpriteAnimationError as Error;
 
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn node_id_from_str() {
+        assert_eq!("NodeID(0x1)".parse::<NodeID>().unwrap(), NodeID::from_u64(1));
+        assert_eq!("NodeID(\"0x00000000000000FF\")".parse::<NodeID>().unwrap(), NodeID::from_u64(0xFF));
+        assert_eq!("12".parse::<NodeID>().unwrap(), NodeID::from_u64(12));
+        assert_eq!("NodeID(from_str_node)".parse::<NodeID>().unwrap(), NodeID::from_name("from_str_node"));
+        assert!("NodeID(0xZZ".parse::<NodeID>().is_err());
+        assert!("NodeID(0xZZ)".parse::<NodeID>().is_err());
+        assert!(matches!("NodeID()".parse::<NodeID>(), Err(Error::ParseError { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn bad_ids_are_load_errors() {
+        assert!(ron::from_str::<NodeID>("NodeID(\"0xZZ\")").is_err());
+        assert!(ron::from_str::<NodeID>("NodeID(\"\")").is_err());
+    }
+}
+
 pub trait AnimationNode: Send + Sync + Any
 {
     fn run(&self, state: &mut super::state::AnimationState) -> NodeResult;
@@ -19,6 +42,37 @@ pub trait AnimationNode: Send + Sync + Any
     fn node_type(&self) -> String;
     #[cfg(feature = "hash")]
     fn hash(&self) -> u64;
+    /// every node this node can return as `NodeResult::Next` with a label saying when
+    /// nodes that return none are treated as ending in `NodeResult::Done`
+    fn edges(&self) -> Vec<NodeEdge> {
+        Vec::new()
+    }
+    /// the `NodeID` of every edge
+    fn next_nodes(&self) -> Vec<NodeID> {
+        self.edges().into_iter().map(|edge| edge.next).collect()
+    }
+    /// how many frames the node plays; None for nodes that go to another node
+    fn frame_count(&self) -> Option<usize> {
+        None
+    }
+    /// the sheet the node's atlas frames are cut from; the tree builds its atlas when the node is inserted
+    fn sprite_sheet(&self) -> Option<&crate::sprite_sheet::SpriteSheet> {
+        None
+    }
+}
+
+/// A node another node can go to
+/// `label` is what picks it, such as "then", "default", a match value or a script condition
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct NodeEdge {
+    pub next: NodeID,
+    pub label: String,
+}
+
+impl NodeEdge {
+    pub fn new(next: NodeID, label: impl Into<String>) -> NodeEdge {
+        NodeEdge { next, label: label.into() }
+    }
 }
 
 pub trait CanLoad {
@@ -28,13 +82,7 @@ pub trait CanLoad {
 #[derive(Debug, Default ,Hash, PartialEq, Eq, Clone, Copy, Reflect)]
 pub struct NodeID(u64);
 
-use std::{collections::HashMap, any::Any};
-lazy_static::lazy_static! {
-    static ref NODE_ID_NAMES: std::sync::Mutex<HashMap<NodeID, String>> = {
-        let map = HashMap::new();
-        std::sync::Mutex::new(map)
-    };
-}
+use std::any::Any;
 
 impl NodeID {
     pub fn as_u64(self) -> u64 {
@@ -43,67 +91,40 @@ impl NodeID {
     pub fn from_u64(id: u64) -> Self {
         NodeID(id)
     }
-    pub fn from_str(data: &str) -> NodeID {
-        let data = data.trim();
-        let data = if data.starts_with("NodeID(") {
-            if !data.ends_with(')') {
-                panic!("NodeID: started with 'NodeID(' but did not end with ')'");
-            }
-            &data[7..data.len() - 1]
-        } else {
-            data
-        };
-        let id = if data.starts_with(|c: char| {c.is_digit(10)}) {
-            NodeID::from_digit(data)
-        } else {
-           NodeID::from_name(data)
-        };
-        id
-    }
-
+    /// the id for `name`
+    /// the name is only kept by the `AnimationNodeTree` loading it, use `try_from_name` to keep and check it yourself
     pub fn from_name(name: &str) -> NodeID{
         let name = name.trim();
         let id = NodeID::hash_name(name);
-        let mut names = NODE_ID_NAMES.lock().unwrap();
-        if !names.contains_key(&id) {
-            names.insert(id, name.to_string());
-        }
+        crate::names::record_node(id, name);
         id
     }
 
+    /// the id for `name`, adding it to `names` such as `AnimationNodeTree::names_mut`
+    /// or `NameCollision` if a different name already has the id
+    pub fn try_from_name(name: &str, names: &mut crate::names::NameRegistry) -> Result<NodeID, Error> {
+        names.node_id(name)
+    }
+
+    /// the id of a name; stable across Rust versions, see `StableHasher`
     fn hash_name(name: &str) -> NodeID {
-        use std::hash::Hash;
         use std::hash::Hasher;
-        let mut hasher = std::collections::hash_map::DefaultHasher::default();
-        name.hash(&mut hasher);
+        let mut hasher = crate::hash::StableHasher::default();
+        hasher.write(name.as_bytes());
         while hasher.finish() < 65536 {
             hasher.write_u8(0);
         }
         NodeID(hasher.finish())
     }
 
-    fn from_digit(from: &str) -> NodeID {
-        let from = from.trim();
-        if from.starts_with("0x") || from.starts_with("0X") {
-            NodeID::from_u64(u64::from_str_radix(&from[2..], 16).expect("NodeID: failed to parse hexadecimal"))
-        } 
-        else if from.starts_with("0b") || from.starts_with("0B") {
-            NodeID::from_u64(u64::from_str_radix(&from[2..], 2).expect("NodeID: failed to parse binary"))
-        }
-        else if from.starts_with("0o") || from.starts_with("0O") {
-            NodeID::from_u64(u64::from_str_radix(&from[2..], 8).expect("NodeID: failed to parse octal"))
-        }
-        else {
-            NodeID::from_u64(u64::from_str_radix(from, 10).expect("NodeID: failed to parse decimal"))
-        }
+    /// the name of the id in `names`, such as `AnimationNodeTree::names`
+    pub fn name_in<'a>(&self, names: &'a crate::names::NameRegistry) -> Option<&'a str> {
+        names.node_name(*self)
     }
 
+    /// the name of the id in the tree it is being shown by, see `AnimationNodeTree::display`
     pub fn name(&self) -> Option<String> {
-        if let Some(v) = NODE_ID_NAMES.lock().unwrap().get(self) {
-            Some(v.clone())
-        } else {
-            None
-        }
+        crate::names::shown_node_name(*self)
     }
 
     pub fn name_or_id(&self) -> String {
@@ -134,8 +155,7 @@ mod serde {
         where
             D: serde::Deserializer<'de> {
             let r = NodeID::deserialize(deserializer)?;
-            let res = u64::from_str_radix(&r.0[2..], 16);
-            if let Ok(id) = res {Ok(Self(id))} else {bevy::log::error!("NodeID deserialize error {:?}", res); Ok(Self(0))}
+            r.0.parse().map_err(serde::de::Error::custom)
         }
     }
 }
@@ -150,6 +170,28 @@ impl bevy_inspector_egui::Inspectable for NodeID {
     }
 }
 
+impl std::str::FromStr for NodeID {
+    type Err = Error;
+
+    /// `NodeID(..)` with a name or number, or a bare name or number; the name or number may be in quotes
+    fn from_str(data: &str) -> Result<NodeID, Error> {
+        let data = data.trim();
+        let error = |message: &str| Error::ParseError { kind: "NodeID", input: data.to_string(), message: message.to_string() };
+        let inner = match data.strip_prefix("NodeID(") {
+            Some(inner) => inner.strip_suffix(')').ok_or_else(|| error("started with `NodeID(` but did not end with `)`"))?,
+            None => data,
+        };
+        let inner = inner.trim().trim_matches('"').trim();
+        if inner.is_empty() {
+            Err(error("needs a name or number"))
+        } else if inner.starts_with(|c: char| c.is_ascii_digit()) {
+            crate::hash::parse_u64(inner).map(NodeID).ok_or_else(|| error("invalid number"))
+        } else {
+            Ok(NodeID::from_name(inner))
+        }
+    }
+}
+
 impl std::fmt::Display for NodeID {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("NodeID({:#020X})",self.0))
@@ -159,10 +201,30 @@ impl std::fmt::Display for NodeID {
 #[derive(Debug)]
 pub enum NodeResult {
     Next(NodeID),
-    Done(Handle<Image>),
+    Done(Frame),
     Error(String),
 }
 
+/// The image shown for one frame of an animation
+/// either its own image or an index into a `TextureAtlas`
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Reflect)]
+pub enum Frame {
+    Image(Handle<Image>),
+    Atlas(Handle<TextureAtlas>, usize),
+}
+
+impl From<Handle<Image>> for Frame {
+    fn from(handle: Handle<Image>) -> Frame {
+        Frame::Image(handle)
+    }
+}
+
+impl From<(Handle<TextureAtlas>, usize)> for Frame {
+    fn from((atlas, index): (Handle<TextureAtlas>, usize)) -> Frame {
+        Frame::Atlas(atlas, index)
+    }
+}
+
 impl std::fmt::Display for NodeResult{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
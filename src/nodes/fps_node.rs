@@ -64,14 +64,14 @@ impl AnimationNode for FPSNode {
     }
 
     fn run(&self, state: &mut AnimationState) -> NodeResult {
-        let delta = state.get_attribute::<f32>(&Attribute::DELTA);
-        let rem_time = state.try_get_attribute_or_error::<f32>(&Attribute::TIME_ON_FRAME).unwrap_or(0.);
+        let delta = state.get(TypedAttribute::DELTA);
+        let rem_time = state.try_get(TypedAttribute::TIME_ON_FRAME).unwrap_or(0.);
         let time = delta + rem_time;
         let frames = (time / self.frame_time()).floor();
         let rem_time = time - self.frame_time() * frames;
-        state.set_attribute(Attribute::FRAMES, frames as usize);
-        state.set_attribute(Attribute::TIME_ON_FRAME, rem_time);
-        state.set_attribute(Attribute::LAST_FPS, self.frame_time());
+        state.set(TypedAttribute::FRAMES, frames as usize);
+        state.set(TypedAttribute::TIME_ON_FRAME, rem_time);
+        state.set(TypedAttribute::LAST_FPS, self.frame_time());
         NodeResult::Next(self.then)
     }
 
@@ -109,6 +109,10 @@ impl AnimationNode for FPSNode {
     fn id(&self) -> NodeID {
         NodeID::from_name(&self.name)
     }
+
+    fn edges(&self) -> Vec<NodeEdge> {
+        vec![NodeEdge::new(self.then, "then")]
+    }
 }
 
 #[cfg(feature = "serialize")]
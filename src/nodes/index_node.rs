@@ -1,7 +1,5 @@
 use crate::node_core::CanLoad;
 use crate::prelude::*;
-use bevy::prelude::Handle;
-use bevy::prelude::Image;
 use bevy::reflect::Reflect;
 use crate::error::BevySpriteAnimationError as Error;
 
@@ -125,14 +123,65 @@ mod test {
         let test_node = test_node.unwrap();
         assert_eq!(test_node.hash(), true_node.hash())
     }
+
+    #[test]
+    fn markers_on_skipped_frames() {
+        use super::IndexNode;
+        use crate::node_core::AnimationNode;
+        use crate::prelude::{AnimationState, Attribute};
+        let asset_server = test_asset_server();
+        let mut handles = Vec::new();
+        for i in 0..5 {
+            handles.push(asset_server.load(format!("Zombie1/zombie1_{:05}.png", i)));
+        }
+        let node = IndexNode::new("Zombie1_Walk", &handles, true)
+            .with_marker(0, "start")
+            .with_marker(2, "footstep")
+            .with_marker(3, "footstep");
+        let mut state = AnimationState::default();
+        node.run(&mut state);
+        assert_eq!(state.markers.drain(..).collect::<Vec<_>>(), vec![("start".to_string(), 0)]);
+        state.set_attribute(Attribute::FRAMES, 6usize);
+        node.run(&mut state);
+        assert_eq!(state.markers, vec![
+            ("footstep".to_string(), 2),
+            ("footstep".to_string(), 3),
+            ("start".to_string(), 0),
+            ]);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize_deserialize_sheet() {
+        use super::IndexNode;
+        use crate::node_core::NodeLoader;
+        use crate::node_core::AnimationNode;
+        use crate::prelude::SpriteSheet;
+        use super::IndexNodeLoader;
+        let asset_server = test_asset_server();
+        let sheet = SpriteSheet::grid("Zombie1/Zombie1_sheet.png", bevy::prelude::Vec2::splat(64.), 8, 9);
+        let true_node: Box<dyn AnimationNode> = Box::new(IndexNode::from_sheet("Zombie1_Idle", sheet, &[0, 1, 2], true, &asset_server));
+        let mut res = String::new();
+        let ser_res = true_node.serialize(&mut res, &asset_server);
+        assert!(ser_res.is_ok(), "{}", ser_res.err().unwrap());
+        let mut loader = IndexNodeLoader;
+        let test_node = loader.load(&res, &asset_server);
+        assert!(test_node.is_ok(), "{}", test_node.err().unwrap());
+        assert_eq!(test_node.unwrap().hash(), true_node.hash())
+    }
 }
 
 #[derive(Debug, Reflect, std::hash::Hash)]
 pub struct IndexNode{
     name: String,
-    frames: Vec<Handle<Image>>,
+    frames: Vec<Frame>,
     is_loop: bool,
     index: Attribute,
+    /// the sheet the atlas frames were cut from; needed to serialize them
+    #[reflect(ignore)]
+    sheet: Option<SpriteSheet>,
+    /// named markers sent as `AnimationMarker` events when their frame is reached
+    markers: Vec<(usize, String)>,
 }
 
 #[cfg(feature = "bevy-inspector-egui")]
@@ -154,24 +203,49 @@ impl bevy_inspector_egui::Inspectable for IndexNode {
 
 impl IndexNode {
     #[inline(always)]
-    pub fn new(name: &str, frames: &[Handle<Image>], is_loop: bool) -> IndexNode{
+    pub fn new<T: Into<Frame> + Clone>(name: &str, frames: &[T], is_loop: bool) -> IndexNode{
         IndexNode { 
             name: name.to_string(),
-            frames: frames.to_vec(),
+            frames: frames.iter().cloned().map(Into::into).collect(),
             is_loop,
             index: Attribute::INDEX,
+            sheet: None,
+            markers: Vec::new(),
         }
     }
 
     #[inline(always)]
-    pub fn new_with_index(name: &str, frames: &[Handle<Image>], is_loop: bool, index: Attribute) -> IndexNode {
+    pub fn new_with_index<T: Into<Frame> + Clone>(name: &str, frames: &[T], is_loop: bool, index: Attribute) -> IndexNode {
         IndexNode { 
             name: name.to_string(),
-            frames: frames.to_vec(),
+            frames: frames.iter().cloned().map(Into::into).collect(),
             is_loop,
             index,
+            sheet: None,
+            markers: Vec::new(),
         }
     }
+
+    /// uses the frames at `indices` in the atlas built from `sheet`
+    #[cfg(feature = "serialize")]
+    pub fn from_sheet(name: &str, sheet: SpriteSheet, indices: &[usize], is_loop: bool, asset_server: &bevy::prelude::AssetServer) -> IndexNode {
+        let atlas = sheet.load(asset_server);
+        IndexNode {
+            name: name.to_string(),
+            frames: indices.iter().map(|i| Frame::Atlas(atlas.clone(), *i)).collect(),
+            is_loop,
+            index: Attribute::INDEX,
+            sheet: Some(sheet),
+            markers: Vec::new(),
+        }
+    }
+
+    /// sends an `AnimationMarker` named `marker` each time `frame` is reached
+    pub fn with_marker(mut self, frame: usize, marker: &str) -> IndexNode {
+        self.markers.push((frame, marker.to_string()));
+        self.markers.sort();
+        self
+    }
 }
 
 #[cfg(feature = "serialize")]
@@ -186,14 +260,39 @@ impl AnimationNode for IndexNode {
         &self.name
     }
 
+    fn sprite_sheet(&self) -> Option<&SpriteSheet> {
+        self.sheet.as_ref()
+    }
+
     fn node_type(&self) -> String {
         "IndexNode".to_string()
     }
 
+    fn frame_count(&self) -> Option<usize> {
+        Some(self.frames.len())
+    }
+
     fn run(&self, state: &mut AnimationState) -> NodeResult {
         assert!(self.frames.len() != 0);
-        let mut index = state.try_get_attribute::<usize>(&self.index).unwrap_or(0);
-        let frames = state.get_attribute::<usize>(&Attribute::FRAMES);
+        let start = state.try_get_attribute::<usize>(&self.index);
+        let mut index = start.unwrap_or(0);
+        let frames = state.get(TypedAttribute::FRAMES);
+        if !self.markers.is_empty() {
+            if start.is_none() {
+                crate::nodes::reach_frame(&self.markers, 0, state);
+            }
+            let mut frame = index;
+            for _ in 0..frames {
+                frame += 1;
+                if frame >= self.frames.len() {
+                    if !self.is_loop {
+                        break;
+                    }
+                    frame %= self.frames.len();
+                }
+                crate::nodes::reach_frame(&self.markers, frame, state);
+            }
+        }
         index += frames;
         if index >= self.frames.len() {
             if self.is_loop {
@@ -217,19 +316,25 @@ impl AnimationNode for IndexNode {
         data.push_str("IndexNode(\n\t");
         data.push_str("name: \"");
         data.push_str(&self.name);
-        data.push_str("\",\n\tframes: [\n\t");
+        data.push_str("\",\n\t");
+        if let Some(sheet) = &self.sheet {
+            data.push_str("sheet: ");
+            data.push_str(&ron::to_string(sheet)?);
+            data.push_str(",\n\t");
+        }
+        data.push_str("frames: [\n\t");
         for frame in self.frames.iter() {
-            if let Some(path) = asset_server.get_handle_path(frame) {
-                data.push_str(path.path().to_str().unwrap())
-            } else {
-                return Err(Error::AssetPathNotFound(frame.clone_weak()));
-            }
+            data.push_str(&crate::nodes::serialize_frame(frame, self.sheet.as_ref(), asset_server)?);
             data.push_str(",\n\t");
         }
         data.push_str("],\n\t");
         data.push_str(&format!("is_loop: {},\n\t",self.is_loop));
         data.push_str("index: ");
         data.push_str(&ron::to_string(&self.index)?);
+        if !self.markers.is_empty() {
+            data.push_str(",\n\tmarkers: ");
+            data.push_str(&ron::to_string(&self.markers)?);
+        }
         data.push_str(",\n\t),\n");
         Ok(())
     }
@@ -253,8 +358,7 @@ pub use loader::IndexNodeLoader;
 
 #[cfg(feature = "serialize")]
 mod loader {
-use crate::{node_core::NodeLoader, prelude::Attribute};
-use std::collections::HashMap;
+use crate::{node_core::NodeLoader, prelude::{Attribute, SpriteSheet}};
 use super::IndexNode;
 
 use crate::prelude::{AnimationNode, BevySpriteAnimationError as Error};
@@ -264,66 +368,35 @@ impl NodeLoader for IndexNodeLoader {
     fn load(&mut self, data: &str, asset_server: &bevy::prelude::AssetServer) -> Result<Box<dyn AnimationNode>, Error> {
         let data = data.trim();
         let data = if data.starts_with("IndexNode(") {&data[10..]} else {data};
-        let mut chars = data.chars().peekable();
-        let mut map: HashMap<&str, &str> = HashMap::new();
-        let mut start = 0;
-        let mut len = 0;
-        let mut key = "";
-        let mut is_key = true;
-        if let Some(c) = chars.peek() {if *c == '(' {start += 1; chars.next();}}
-        while let Some(c) = chars.next() {
-            match c {
-                ':' => {if is_key {
-                    key = &data[start..start+len].trim();
-                    start += len + 1;
-                    len = 0;
-                    is_key = false;
-                    }
-                },
-                ',' => if !is_key {
-                    //info!("add {} : {}", key, data[start..start+len].trim());
-                    map.insert(key, data[start..start+len].trim());
-                    start += len + 1;
-                    len = 0;
-                    is_key = true;
-                    key = "";
-                }
-                '[' => {
-                    while let Some(c) = chars.next() {
-                        len += 1;
-                        if c == ']' {
-                            len += 1;
-                            break;
-                        }
-                    }
-                }
-                _ => {
-                    len += 1;
-                }
-            }
-        }
-        if len > 0 {
-            map.insert(key, data[start..start+len].trim());
-        }
+        let map = crate::nodes::node_fields(data);
+        let sheet = match map.get("sheet") {
+            Some(v) => {
+                let sheet: SpriteSheet = ron::from_str(v)?;
+                Some((sheet.load(asset_server), sheet))
+            },
+            None => None,
+        };
         let mut frames = Vec::new();
-        for path in if let Some(paths) = map.get("frames") {
-            paths[1..paths.len() - 1].split_terminator(',')
-        } else {
+        let Some(paths) = map.get("frames") else {
             return Err(Error::DeserializeError{
                 node_type: "IndexNode",
                 message: "Failed to find frames".to_string(),
                 loc: crate::here!()});
-        } {
-            if path.trim().len() == 0 {
-                continue;
-            }
-            frames.push(asset_server.load(path[0..path.len()].trim()))
+        };
+        for path in crate::nodes::index_frames(paths) {
+            frames.push(crate::nodes::load_frame(path, sheet.as_ref().map(|(atlas, _)| atlas), asset_server)?)
         }
 
         let index = match map.get("index") {
             Some(v) => {ron::from_str(v)?},
             None => {Attribute::INDEX}
         };
+
+        let mut markers: Vec<(usize, String)> = match map.get("markers") {
+            Some(v) => {ron::from_str(v)?},
+            None => {Vec::new()}
+        };
+        markers.sort();
         
         let is_loop = match map.get("is_loop") {
             Some(v) => {!v.trim().starts_with("f")},
@@ -343,7 +416,9 @@ impl NodeLoader for IndexNodeLoader {
             name,
             frames,
             index,
-            is_loop
+            is_loop,
+            sheet: sheet.map(|(_, sheet)| sheet),
+            markers,
         }))
     }
 
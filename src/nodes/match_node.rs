@@ -50,23 +50,23 @@ impl<T:MatchType> MatchNode<T> {
 }
 
 #[cfg(feature = "serialize")]
-impl<T: MatchType + serde::Serialize + serde::de::DeserializeOwned + Ord> CanLoad for MatchNode<T> {
+impl<T: MatchType + serde::Serialize + serde::de::DeserializeOwned + Ord + std::fmt::Debug> CanLoad for MatchNode<T> {
     fn loader() -> Box<dyn NodeLoader> {
         Box::new(MatchNodeLoader::<T>::default())
     }
 }
 
 impl<T> AnimationNode for MatchNode<T>
-where T:MatchType + serde::de::DeserializeOwned + serde::Serialize + std::any::Any + Ord
+where T:MatchType + serde::de::DeserializeOwned + serde::Serialize + std::any::Any + Ord + std::fmt::Debug
 {
     fn run(&self, state: &mut crate::state::AnimationState) -> NodeResult {
 
-        let val = match state.try_get_attribute_or_error::<T>(&self.check) {
+        let val = match state.try_get_attribute_ref_or_error::<T>(&self.check) {
             Ok(x) => x,
             Err(e) => return NodeResult::Error(format!("{}",e)),
         };
 
-        if let Some(next) = self.pairs.get(&val) {
+        if let Some(next) = self.pairs.get(val) {
             NodeResult::Next(*next)
         } else {
             NodeResult::Next(self.default)
@@ -120,6 +120,20 @@ where T:MatchType + serde::de::DeserializeOwned + serde::Serialize + std::any::A
     fn id(&self) -> NodeID {
         NodeID::from_name(&self.name)
     }
+
+    fn edges(&self) -> Vec<NodeEdge> {
+        let mut pairs: Vec<(&T, &NodeID)> = self.pairs.iter().collect();
+        pairs.sort_by(|a, b| a.0.cmp(b.0));
+        let mut edges: Vec<NodeEdge> = pairs.into_iter().map(|(value, next)| {
+            #[cfg(feature = "ron")]
+            let label = ron::to_string(value).unwrap_or_else(|_| format!("{:?}", value));
+            #[cfg(not(feature = "ron"))]
+            let label = format!("{:?}", value);
+            NodeEdge::new(*next, label)
+        }).collect();
+        edges.push(NodeEdge::new(self.default, "default"));
+        edges
+    }
 }
 
 #[cfg(feature = "serialize")]
@@ -133,6 +147,14 @@ mod loader {
         assert_eq!("MatchNode<u32>", mnl.can_load()[0]);
     }
 
+    #[test]
+    fn edges_are_labelled_by_value() {
+        use crate::node_core::AnimationNode;
+        let node = MatchNode::<u32>::new("edges_by_value", vec![(2, NodeID::from_u64(2)), (1, NodeID::from_u64(1))], Attribute::INDEX, NodeID::from_u64(3));
+        let labels: Vec<String> = node.edges().into_iter().map(|edge| edge.label).collect();
+        assert_eq!(labels, vec!["1", "2", "default"]);
+    }
+
     use core::marker::PhantomData;
 
     use crate::node_core::NodeLoader;
@@ -157,7 +179,7 @@ mod loader {
     }
     //012345678901234567890123456789012345678901
     //bevy_sprite_animation::nodes::match_node::
-    impl<T> NodeLoader for MatchNodeLoader<T> where T:MatchType + std::any::Any + serde::de::DeserializeOwned + serde::Serialize + Ord {
+    impl<T> NodeLoader for MatchNodeLoader<T> where T:MatchType + std::any::Any + serde::de::DeserializeOwned + serde::Serialize + Ord + std::fmt::Debug {
         fn load(&mut self, data: &str, _asset_server: &bevy::prelude::AssetServer) -> Result<Box<dyn crate::prelude::AnimationNode>, crate::error::BevySpriteAnimationError> {
         use std::collections::HashMap;
         let data = data.trim();
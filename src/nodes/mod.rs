@@ -9,4 +9,122 @@ pub mod index_node;
 pub mod fps_node;
 pub mod match_node;
 pub mod script_node;
-pub mod scale_node;
\ No newline at end of file
+pub mod scale_node;
+
+use crate::prelude::AnimationState;
+
+/// adds every marker on `frame` to `state`
+pub(crate) fn reach_frame(markers: &[(usize, String)], frame: usize, state: &mut AnimationState) {
+    for (_, marker) in markers.iter().filter(|(f, _)| *f == frame) {
+        state.add_marker(marker, frame);
+    }
+}
+
+#[cfg(feature = "serialize")]
+use bevy::prelude::{AssetServer, Handle, TextureAtlas};
+#[cfg(feature = "serialize")]
+use crate::prelude::{BevySpriteAnimationError as Error, Frame, SpriteSheet};
+
+/// writes a frame as its image path or as its index in `sheet` if it is an atlas frame
+#[cfg(feature = "serialize")]
+pub(crate) fn serialize_frame(frame: &Frame, sheet: Option<&SpriteSheet>, asset_server: &AssetServer) -> Result<String, Error> {
+    match frame {
+        Frame::Image(handle) => match asset_server.get_handle_path(handle) {
+            Some(path) => Ok(path.path().to_str().unwrap().to_string()),
+            None => Err(Error::AssetPathNotFound(handle.clone_weak())),
+        },
+        Frame::Atlas(atlas, index) => match sheet {
+            Some(sheet) if sheet.handle_id() == atlas.id() => Ok(index.to_string()),
+            _ => Err(Error::SpriteSheetNotFound(atlas.clone_weak())),
+        },
+    }
+}
+
+/// reads a frame as an image path or as an index into `atlas` if the node has a sheet
+#[cfg(feature = "serialize")]
+pub(crate) fn load_frame(data: &str, atlas: Option<&Handle<TextureAtlas>>, asset_server: &AssetServer) -> Result<Frame, Error> {
+    let data = data.trim();
+    match atlas {
+        Some(atlas) => Ok(Frame::Atlas(atlas.clone(), data.parse()?)),
+        None => Ok(Frame::Image(asset_server.load(data))),
+    }
+}
+
+/// splits the fields of a node written as `(key: value, ..)` into a map of key to value
+#[cfg(feature = "serialize")]
+pub(crate) fn node_fields(data: &str) -> std::collections::HashMap<&str, &str> {
+    let mut chars = data.chars().peekable();
+    let mut map = std::collections::HashMap::new();
+    let mut start = 0;
+    let mut len = 0;
+    let mut key = "";
+    let mut is_key = true;
+    if let Some(c) = chars.peek() {if *c == '(' {start += 1; chars.next();}}
+    while let Some(c) = chars.next() {
+        match c {
+            ':' => {if is_key {
+                key = data[start..start+len].trim();
+                start += len + 1;
+                len = 0;
+                is_key = false;
+                }
+            },
+            ',' => if !is_key {
+                map.insert(key, data[start..start+len].trim());
+                start += len + 1;
+                len = 0;
+                is_key = true;
+                key = "";
+            }
+            '[' => {
+                for c in chars.by_ref() {
+                    len += 1;
+                    if c == ']' {
+                        len += 1;
+                        break;
+                    }
+                }
+            }
+            '(' if !is_key => {
+                len += 1;
+                let mut depth = 1;
+                for c in chars.by_ref() {
+                    len += 1;
+                    match c {
+                        '(' => depth += 1,
+                        ')' => {depth -= 1; if depth == 0 {break;}},
+                        _ => {},
+                    }
+                }
+            }
+            _ => {
+                len += 1;
+            }
+        }
+    }
+    if len > 0 {
+        map.insert(key, data[start..start+len].trim());
+    }
+    map
+}
+
+/// the entries of an `IndexNode` frames list, `[path, ..]`
+#[cfg(feature = "serialize")]
+pub(crate) fn index_frames(frames: &str) -> impl Iterator<Item = &str> {
+    frames[1..frames.len() - 1].split_terminator(',').map(str::trim).filter(|path| !path.is_empty())
+}
+
+/// the entries of a `VariableNode` frames list, `[(path, time), ..]`
+#[cfg(feature = "serialize")]
+pub(crate) fn variable_frames(frames: &str) -> Result<Vec<(&str, f32)>, Error> {
+    let mut entries = Vec::new();
+    let mut paths = frames[1..frames.len() - 1].split_terminator(',');
+    while let Some(path) = paths.next() {
+        let path = path.trim();
+        if path.is_empty() {continue;}
+        let time = paths.next().ok_or(Error::DeserializeError { node_type: "VariableNode", message: "Frame Time not found".into(), loc: path.to_string() })?.trim();
+        let time = time[..time.len()-1].parse::<f32>().map_err(Error::ParseFloatError)?;
+        entries.push((path[1..].trim(), time));
+    }
+    Ok(entries)
+}
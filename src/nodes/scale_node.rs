@@ -182,9 +182,9 @@ impl AnimationNode for ScaleNode {
 
     fn run(&self, state: &mut AnimationState) -> NodeResult {
         let mut index = state.try_get_attribute::<usize>(&self.index).unwrap_or(0);
-        let rem_time = state.get_attribute::<f32>(&Attribute::TIME_ON_FRAME);
-        let frames = state.get_attribute::<usize>(&Attribute::FRAMES);
-        let last = state.get_attribute::<f32>(&Attribute::LAST_FPS);
+        let rem_time = state.get(TypedAttribute::TIME_ON_FRAME);
+        let frames = state.get(TypedAttribute::FRAMES);
+        let last = state.get(TypedAttribute::LAST_FPS);
         let scale = state.try_get_attribute::<f32>(&self.scale).unwrap_or(1.);
         let mut frame_time = last * frames as f32 + rem_time;
         let width = last * scale;
@@ -192,9 +192,9 @@ impl AnimationNode for ScaleNode {
         frame_time -= frames * width;
         index += frames as usize;
 
-        state.set_attribute(Attribute::LAST_FPS, last * scale);
-        state.set_attribute(Attribute::TIME_ON_FRAME, frame_time);
-        state.set_attribute(Attribute::FRAMES, frames as usize);
+        state.set(TypedAttribute::LAST_FPS, last * scale);
+        state.set(TypedAttribute::TIME_ON_FRAME, frame_time);
+        state.set(TypedAttribute::FRAMES, frames as usize);
         state.set_attribute(self.index, index);
         NodeResult::Next(self.next)
     }
@@ -232,6 +232,10 @@ impl AnimationNode for ScaleNode {
     fn id(&self) -> NodeID {
         NodeID::from_name(&self.name)
     }
+
+    fn edges(&self) -> Vec<NodeEdge> {
+        vec![NodeEdge::new(self.next, "next")]
+    }
 }
 
 #[cfg(feature = "serialize")]
@@ -1,10 +1,16 @@
 use bevy::prelude::AssetServer;
-use bevy::log::error;
-use crate::{prelude::{NodeID, Attribute, AnimationNode, NodeResult, BevySpriteAnimationError}, state::AnimationState};
+use crate::{prelude::{NodeID, NodeEdge, Attribute, AnimationNode, NodeResult, BevySpriteAnimationError}, state::AnimationState};
+
+use parser::{ArithOp, CmpOp, Expr, Func, Script, Stmt};
+
+mod parser;
+mod vm;
+
+pub(crate) use vm::Scratch;
 
 #[cfg(test)]
 mod test {
-    use crate::test::test_asset_server;
+    use crate::test::{with_indices, test_asset_server};
     use super::*;
 
     #[test]
@@ -14,83 +20,276 @@ mod test {
         let asset_server = test_asset_server();
         let node = ScriptNode::new("#id NodeID(0x1) #fallback NodeID(Zombie{i}_Idle) if Index(Stand) >= 6 set Attribute(ZombieState) Ron(Idle) return NodeID(Zombie1_StandF)");
 
-        
+
         let mut data = String::new();
         node.serialize(&mut data, &asset_server).unwrap();
         println!("\n\n{}\n\n", data);
         let test_node = ScriptNode::loader().load(&mut data, &asset_server).unwrap();
         assert_eq!(node.hash(), test_node.hash());
     }
-}
 
-impl AnimationNode for ScriptNode {
-    fn run(&self, state: &mut crate::state::AnimationState) -> NodeResult {
-        let mut index = 0;
-        while index < self.tokens.len() {
-            match self.tokens[index] {
-                Token::If => {
-                    if if_condishion(state, &self.tokens[index + 1], &self.tokens[index + 2], &self.tokens[index + 3]) {
-                        index += 4;
-                    } else {
-                        index += 7;
-                    }
-                }
-                Token::Set => {
-                    let key = match self.tokens[index + 1] {
-                        Token::Index(i) => i,
-                        Token::Attribute(i) => i,
-                        _ => {panic!("unimplemented `set {:?}`", self.tokens[index + 1])}
-                    };
-                    match &self.tokens[index + 2] {
-                        Token::Raw(v) => {
-                            let data = state.get_attribute_raw_mut(&key);
-                            *data = v.clone();},
-                        Token::Ron(data) => {
-                            #[cfg(feature = "ron")]
-                            {
-                                state.set_attribute_from_ron(key, data).unwrap();
-                            }
-                            #[cfg(not(feature = "ron"))]
-                            {
-                                return Err(NodeResult::Error(format!("tried to set {:?} = Ron({}) without ron feature", key, data)));
-                            }
-                        },
-                        Token::Int(val) => {
-                            if key.is_index() {
-                                state.set_attribute(key, *val);
-                            } else {
-                                panic!("unimplemented only Index(_) can be set to int")
-                            }
-                        }
-                        _ => panic!("unimplemented `set {:?} = {:?}`", key, self.tokens[index + 2]),
-                    }
-                    index += 3;
-                },
-                Token::Return(id) => {
-                    return NodeResult::Next(id);
-                }
-                _ => {
-                    bevy::log::info!("pointer {}", index);
-                    bevy::log::info!("statck = {:?}", self.tokens);
-                    todo!("Token {:?}", self.tokens[index])
-                }
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn serialize_deserialize_logic() {
+        use crate::node_core::CanLoad;
+        let asset_server = test_asset_server();
+        for script in [
+            "if Index(Fall) >= 5 and not Index(Stun) == 1 return NodeID(0x1)",
+            "if Index(Fall) >= 5 or Index(Stun) == 1 and Index(Stand) < 2 return NodeID(0x1)",
+            "if (Index(Fall) >= 5 or Index(Stun) == 1) and not (Index(Stand) < 2 or false) return NodeID(0x1)",
+            "if not not true and (true and (false or true)) return NodeID(0x1)",
+        ] {
+            let node = ScriptNode::new(script);
+            let mut data = String::new();
+            node.serialize(&mut data, &asset_server).unwrap();
+            let test_node = ScriptNode::loader().load(&data, &asset_server).unwrap();
+            assert_eq!(node.hash(), test_node.hash(), "{}", data);
+        }
+    }
+
+    #[test]
+    fn logic() {
+        let mut state = AnimationState::default();
+        state.set_attribute(Attribute::new_index("Fall"), 5usize);
+        state.set_attribute(Attribute::new_index("Stun"), 0usize);
+        let returns = |script: &str, state: &mut AnimationState| {
+            match ScriptNode::new(script).run(state) {
+                NodeResult::Next(id) => Ok(id == NodeID::from_u64(1)),
+                NodeResult::Error(e) => Err(e),
+                NodeResult::Done(_) => Ok(false),
+            }
+        };
+        let fallback = "#fallback NodeID(0x2) ";
+        assert_eq!(returns(&format!("{}if Index(Fall) >= 5 and not Index(Stun) == 1 return NodeID(0x1)", fallback), &mut state), Ok(true));
+        assert_eq!(returns(&format!("{}if Index(Fall) >= 6 or Index(Stun) == 0 return NodeID(0x1)", fallback), &mut state), Ok(true));
+        assert_eq!(returns(&format!("{}if not (Index(Fall) >= 6 or Index(Stun) == 0) return NodeID(0x1)", fallback), &mut state), Ok(false));
+        assert_eq!(returns(&format!("{}if true or false and false return NodeID(0x1)", fallback), &mut state), Ok(true));
+        // short circuit skips the division by zero
+        assert_eq!(returns(&format!("{}if false and 1 / 0 == 1 return NodeID(0x1)", fallback), &mut state), Ok(false));
+        assert_eq!(returns(&format!("{}if true or 1 / 0 == 1 return NodeID(0x1)", fallback), &mut state), Ok(true));
+        assert!(returns(&format!("{}if true and 1 / 0 == 1 return NodeID(0x1)", fallback), &mut state).is_err());
+        assert!(returns(&format!("{}if 1 and true return NodeID(0x1)", fallback), &mut state).is_err());
+    }
+
+    #[test]
+    fn bytecode_matches_interpreter() {
+        let stand = Attribute::new_index("Stand");
+        for script in [
+            "#fallback NodeID(0x2) if Index(Stand) >= 6 set Index(Fall) 1 if Index(Fall) == 1 return NodeID(0x1)",
+            "#fallback NodeID(0x2) if Index(Stand) >= 3 and not (Index(Fall) == 1 or false) { set Index(Fall) = Index(Stand) * 2 - 1 } else if Index(Stand) == 0 { return NodeID(0x3) } else { set Index(Fall) = 1 }",
+            "if Index(Stand) / (Index(Stand) - 4) > 1 return NodeID(0x1)",
+            "if Index(Stand) or true return NodeID(0x1)",
+            "set Index(Fall) = -Index(Stand)",
+            "if Attribute(ZombieState) == Ron(Idle) return NodeID(0x1)",
+            "set Index(Fall) = rand(0, 9) + clamp(Index(Stand), 2, 5) if chance(0.5) return NodeID(0x1)",
+        ] {
+            let node = ScriptNode::new(script);
+            for start in 0..8usize {
+                let (mut vm, mut walker) = (AnimationState::default(), AnimationState::default());
+                vm.set_attribute(stand, start);
+                walker.set_attribute(stand, start);
+                let (a, b) = (node.run(&mut vm), node.interpret(&mut walker));
+                assert_eq!(format!("{:?}", a), format!("{:?}", b), "{} with Index(Stand) {}", script, start);
+                assert_eq!(vm.try_get_attribute::<usize>(&Attribute::new_index("Fall")), walker.try_get_attribute::<usize>(&Attribute::new_index("Fall")));
             }
         }
-        if let Some(fallback) = self.fallback {
-            bevy::log::warn!("fallback {:?} used", fallback);
-            NodeResult::Next(fallback)
+    }
+
+    #[test]
+    fn builtins() {
+        let mut state = AnimationState::default();
+        let fall = Attribute::new_index("Fall");
+        let value = |script: &str, state: &mut AnimationState| {
+            match ScriptNode::new(&format!("set Index(Fall) = {} return NodeID(0x1)", script)).run(state) {
+                NodeResult::Error(e) => Err(e),
+                _ => Ok(state.get_attribute::<usize>(&fall)),
+            }
+        };
+        assert_eq!(value("min(4, 2, 3)", &mut state), Ok(2));
+        assert_eq!(value("max(4, 2.5) * 2", &mut state), Ok(8));
+        assert_eq!(value("abs(1 - 3)", &mut state), Ok(2));
+        assert_eq!(value("clamp(7, 1, 5) + clamp(-1, 1, 5)", &mut state), Ok(6));
+        assert!(value("clamp(1, 5, 1)", &mut state).is_err());
+        assert!(value("abs(true)", &mut state).is_err());
+        assert!(value("frames_in(NodeID(0x1))", &mut state).is_err());
+        state.frame_counts = std::sync::Arc::new([(NodeID::from_u64(1), 7)].into_iter().collect());
+        assert_eq!(value("frames_in(NodeID(0x1)) - 1", &mut state), Ok(6));
+
+        let node = ScriptNode::new("set Index(Fall) = rand(1, 3) if chance(0) or not chance(1) return NodeID(0x2) return NodeID(0x1)");
+        let mut rolls = Vec::new();
+        state.set_seed(3);
+        for _ in 0..100 {
+            assert!(matches!(node.run(&mut state), NodeResult::Next(id) if id == NodeID::from_u64(1)));
+            rolls.push(state.get_attribute::<usize>(&fall));
         }
-        else {
-            NodeResult::Error("ScriptNode: failed to find a node to return and no fallback was set;\n
-            use #fallback followed by a NodeID at the begging of you script to set a fallback node".to_string())
+        assert!((1..=3).all(|roll| rolls.contains(&roll)));
+        assert!(rolls.iter().all(|roll| (1..=3).contains(roll)));
+        // the same seed rolls the same numbers
+        state.set_seed(3);
+        for roll in rolls {
+            node.run(&mut state);
+            assert_eq!(state.get_attribute::<usize>(&fall), roll);
         }
     }
 
-    fn name(&self) -> &str {
-        for tag in self.tags.iter() {
-            if let Tag::Name(name) = tag {
-                return &name;
+    #[test]
+    fn temporary_attributes() {
+        let landed = Attribute::new_index("Landed");
+        let stand = Attribute::new_index("Stand");
+        let node = ScriptNode::new("
+            if Index(Stand) == 0 { set temp Index(Landed) = 1 }
+            else if Index(Stand) == 1 { persist Index(Landed) }
+            else { clear Index(Landed) }
+            return NodeID(0x1)");
+        for run in [ScriptNode::run, ScriptNode::interpret] {
+            let mut state = AnimationState::default();
+            state.set_attribute(stand, 0usize);
+            run(&node, &mut state);
+            assert!(state.temp.contains(&landed) && state.changed(&landed));
+            assert_eq!(state.try_get_attribute::<usize>(&landed), Some(1));
+            state.set_attribute(stand, 1usize);
+            run(&node, &mut state);
+            assert!(!state.temp.contains(&landed));
+            state.set_attribute(stand, 2usize);
+            run(&node, &mut state);
+            assert_eq!(state.try_get_attribute::<usize>(&landed), None);
+        }
+    }
+
+    #[test]
+    fn emit_events() {
+        let node = ScriptNode::new("#fallback NodeID(0x2) emit step if Index(Stand) >= 1 { emit hit((damage: 5)) return NodeID(0x1) }");
+        let mut state = AnimationState::default();
+        node.run(&mut state);
+        assert_eq!(state.events.drain(..).collect::<Vec<_>>(), vec![("step".to_string(), "()".to_string())]);
+        state.set_attribute(Attribute::new_index("Stand"), 1usize);
+        node.interpret(&mut state);
+        assert_eq!(state.events, vec![
+            ("step".to_string(), "()".to_string()),
+            ("hit".to_string(), "(damage: 5)".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn edges_have_conditions() {
+        let node = ScriptNode::new("#fallback NodeID(0x2) if Index(Stand) >= 6 return NodeID(0x1)");
+        assert_eq!(with_indices(&["Stand"], || node.edges()), vec![
+            NodeEdge::new(NodeID::from_u64(1), "if Index(Stand) >= 6"),
+            NodeEdge::new(NodeID::from_u64(2), "fallback"),
+        ]);
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn loader_reports_line_and_column() {
+        use crate::node_core::CanLoad;
+        let asset_server = test_asset_server();
+        let res = ScriptNode::loader().load("ScriptNode( #fallback NodeID(0x2)\n\tif Index(Stand) >= 6 set Index(Fall) Ron(Idle\n)", &asset_server);
+        let Err(BevySpriteAnimationError::ScriptError { line, column, .. }) = res else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (2, 39));
+    }
+
+    #[test]
+    fn run_does_not_panic() {
+        let mut state = AnimationState::default();
+        let node = ScriptNode::new("set Attribute(run_does_not_panic) [00] return NodeID(0x1)");
+        assert!(matches!(node.run(&mut state), NodeResult::Error(_)));
+        let node = ScriptNode::new("if Attribute(run_does_not_panic) < \"a\" return NodeID(0x1)");
+        assert!(matches!(node.run(&mut state), NodeResult::Error(_)));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut state = AnimationState::default();
+        let stand = Attribute::new_index("Stand");
+        let fall = Attribute::new_index("Fall");
+        state.set_attribute(stand, 2usize);
+        let node = ScriptNode::new("set Index(Fall) = 6 - Index(Stand) if Index(Fall) * 0.5 == 2.0 return NodeID(0x1)");
+        assert!(matches!(node.run(&mut state), NodeResult::Next(id) if id == NodeID::from_u64(1)));
+        assert_eq!(state.get_attribute::<usize>(&fall), 4);
+        let node = ScriptNode::new("set Index(Fall) = (7 - Index(Stand)) / 2 * 3 return NodeID(0x1)");
+        node.run(&mut state);
+        assert_eq!(state.get_attribute::<usize>(&fall), 6);
+        let node = ScriptNode::new("set Index(Fall) = 1 / (Index(Stand) - 2) return NodeID(0x1)");
+        assert!(matches!(node.run(&mut state), NodeResult::Error(_)));
+    }
+
+    #[test]
+    fn else_branches() {
+        let mut state = AnimationState::default();
+        let stand = Attribute::new_index("Stand");
+        let fall = Attribute::new_index("Fall");
+        let node = ScriptNode::new("
+            if Index(Stand) >= 6 {
+                set Index(Fall) = 1
+                set Index(Stand) = 0
+                return NodeID(0x1)
+            } else if Index(Stand) >= 3 {
+                set Index(Fall) = 2
+            } else {
+                set Index(Fall) = 3
+                return NodeID(0x3)
             }
+            return NodeID(0x2)");
+        for (start, next, fall_to, stand_to) in [(7, 1, 1, 0), (4, 2, 2, 4), (1, 3, 3, 1)] {
+            state.set_attribute(stand, start as usize);
+            assert!(matches!(node.run(&mut state), NodeResult::Next(id) if id == NodeID::from_u64(next)));
+            assert_eq!(state.get_attribute::<usize>(&fall), fall_to);
+            assert_eq!(state.get_attribute::<usize>(&stand), stand_to);
+        }
+        assert_eq!(with_indices(&["Stand", "Fall"], || node.edges())[1], NodeEdge::new(NodeID::from_u64(3), "if not Index(Stand) >= 6 and not Index(Stand) >= 3"));
+    }
+
+    #[test]
+    #[cfg(feature = "ron")]
+    fn compare_typed_attributes() {
+        #[derive(serde::Serialize, serde::Deserialize)]
+        enum TestState {
+            Idle,
+            Walking,
+        }
+        let mut state = AnimationState::default();
+        state.set_attribute(Attribute::new_attribute("TestState"), TestState::Walking);
+        state.set_attribute(Attribute::new_attribute("WSpeed"), 1.75f32);
+        state.set_attribute(Attribute::new_attribute("Stunned"), true);
+        state.set_attribute(Attribute::new_attribute("Label"), "run".to_string());
+        let returns = |script: &str, state: &mut AnimationState| {
+            matches!(ScriptNode::new(script).run(state), NodeResult::Next(id) if id == NodeID::from_u64(1))
+        };
+        assert!(returns("if Attribute(TestState) == Ron(Walking) return NodeID(0x1)", &mut state));
+        assert!(returns("if Attribute(TestState) != Ron(Idle) return NodeID(0x1)", &mut state));
+        assert!(returns("if Attribute(WSpeed) > 1.5 return NodeID(0x1)", &mut state));
+        assert!(returns("if Attribute(Stunned) == true return NodeID(0x1)", &mut state));
+        assert!(returns("if Attribute(Label) == \"run\" return NodeID(0x1)", &mut state));
+        assert!(returns("set Attribute(WSpeed) = Attribute(WSpeed) * 2 set Attribute(TestState) = Ron(Idle) return NodeID(0x1)", &mut state));
+        assert_eq!(state.get_attribute::<f32>(&Attribute::new_attribute("WSpeed")), 3.5);
+        assert!(returns("if Attribute(TestState) == Ron(Idle) return NodeID(0x1)", &mut state));
+        assert!(!returns("if Attribute(TestState) == Ron(Running) return NodeID(0x1)", &mut state));
+        assert!(!returns("if Attribute(TestState) > Ron(Idle) return NodeID(0x1)", &mut state));
+    }
+
+    #[test]
+    fn run_sets_and_returns() {
+        let mut state = AnimationState::default();
+        let stand = Attribute::new_index("Stand");
+        let fall = Attribute::new_index("Fall");
+        let node = ScriptNode::new("#fallback NodeID(0x2) if Index(Stand) >= 6 set Index(Fall) 1 if Index(Fall) == 1 return NodeID(0x1)");
+        state.set_attribute(stand, 5usize);
+        assert!(matches!(node.run(&mut state), NodeResult::Next(id) if id == NodeID::from_u64(2)));
+        state.set_attribute(stand, 6usize);
+        assert!(matches!(node.run(&mut state), NodeResult::Next(id) if id == NodeID::from_u64(1)));
+        assert_eq!(state.get_attribute::<usize>(&fall), 1);
+    }
+}
+
+impl AnimationNode for ScriptNode {
+    fn run(&self, state: &mut crate::state::AnimationState) -> NodeResult {
+        self.result(self.program.run(state))
+    }
+
+    fn name(&self) -> &str {
+        if let Some(name) = &self.script.name {
+            return name;
         }
         "unnamed stript; add #name to the first line to add a name"
     }
@@ -104,325 +303,140 @@ impl AnimationNode for ScriptNode {
         "ScriptNode".to_string()
     }
 
+    #[cfg(feature = "hash")]
     fn hash(&self) -> u64 {
         use std::hash::Hash;
         use std::hash::Hasher;
         let mut hasher = std::collections::hash_map::DefaultHasher::new();
-        for tag in self.tags.iter() {
-            Hash::hash(tag, &mut hasher);
-        }
-        for token in self.tokens.iter() {
-            Hash::hash(token, &mut hasher);
-        }
+        Hash::hash(&self.script, &mut hasher);
         hasher.finish()
     }
 
     fn id(&self) -> NodeID {
-        let mut has_name = None;
-        for tag in self.tags.iter() {
-            if let Tag::Name(name) = tag {
-                has_name = Some(name);
-            }
-            if let Tag::ID(id) = tag {
-                return *id;
-            }
+        if let Some(id) = self.script.id {
+            return id;
         }
-        if let Some(name) = has_name {
-            NodeID::from_name(&name)
+        if let Some(name) = &self.script.name {
+            NodeID::from_name(name)
         } else {
-            use std::hash::Hash;
             use std::hash::Hasher;
-            let mut hasher = std::collections::hash_map::DefaultHasher::new();
-            for tag in self.tags.iter() {
-                Hash::hash(tag, &mut hasher);
-            }
-            for token in self.tokens.iter() {
-                Hash::hash(token, &mut hasher);
-            }
+            let mut hasher = crate::hash::StableHasher::default();
+            hasher.write(self.source().as_bytes());
             NodeID::from_u64(hasher.finish())
         }
     }
 
+    fn edges(&self) -> Vec<NodeEdge> {
+        let mut edges = Vec::new();
+        collect_edges(&self.script.body, &mut Vec::new(), &mut edges);
+        if let Some(fallback) = self.script.fallback {
+            edges.push(NodeEdge::new(fallback, "fallback"));
+        }
+        edges
+    }
+
+    #[cfg(feature = "serialize")]
     fn serialize(&self, data: &mut String, _: &AssetServer) -> Result<(), BevySpriteAnimationError> {
         data.push_str("ScriptNode(");
         data.push(' ');
-        for tag in self.tags.iter() {
-            data.push_str(&tag.to_string());
-            data.push('\n');
-        }
-        if self.fallback.is_some() {
-            data.push_str("#fallback ");
-            data.push_str(&self.fallback.unwrap().to_string());
-            data.push('\n');
-        }
-        for token in self.tokens.iter() {
-            data.push_str(&token.to_string());
-            data.push(' ');
-        }
-        data.push_str("),\n\t");
+        data.push_str(&self.source());
+        data.push_str(" ),\n\t");
         Ok(())
     }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq)]
-enum Token {
-    Int(usize),
-    Float(usize),
-    String(String),
-    Raw(Vec<u8>),
-    Plus,
-    Minus,
-    Equals,
-    NotEquals,
-    LessThen,
-    LestThenEq,
-    GratterThen,
-    GratterThenEq,
-    Set,
-    Attribute(Attribute),
-    Index(Attribute),
-    NodeID(NodeID),
-    OpenParen(u8),
-    CloseParen(u8),
-    Multiply,
-    Divide,
-    None,
-    If,
-    Else,
-    Return(NodeID),
-    Ron(String),
-    Unknown(String),
-}
-
-impl ToString for Token {
-    fn to_string(&self) -> String {
-        match self {
-            Token::Return(id) => format!("return {}", id),
-            Token::Int(i) => format!("{}", i),
-            Token::Equals => "==".to_string(),
-            Token::NotEquals => "!=".to_string(),
-            Token::LessThen => "<".to_string(),
-            Token::LestThenEq => "<=".to_string(),
-            Token::GratterThen => ">".to_string(),
-            Token::GratterThenEq => ">=".to_string(),
-            Token::Plus => "+".to_string(),
-            Token::Minus => "-".to_string(),
-            Token::String(s) => format!("\"{}\"", s),
-            Token::Raw(v) => {
-                let mut res = String::with_capacity(v.len() * 2 + 2);
-                res.push('[');
-                for b in v {
-                    res.push_str(&format!("{:#04X}", b)[2..]);
-                }
-                res.push(']');
-                res
-                },
-            Token::If => "if".to_string(),
-            Token::Else => "else".to_string(),
-            Token::Set => "set".to_string(),
-            Token::Attribute(att) => format!("{}", att),
-            Token::Index(att) => format!("{}", att),
-            Token::Ron(data) => format!("Ron({})", data),
-            _ => panic!("unimplemented `to_string` for {:?}", self)
-        }
-    }
-}
-
-#[derive(Debug, Hash, PartialEq, Eq)]
-enum Tag {
-    Name(String),
-    ID(NodeID),
-}
-
-impl ToString for Tag {
-    fn to_string(&self) -> String {
-        match self {
-            Tag::Name(name) => format!("#name \"{}\"", name),
-            Tag::ID(id) => format!("#id {}", id),
+/// every `return` in `stmts` labelled with the conditions that lead to it
+fn collect_edges(stmts: &[Stmt], conditions: &mut Vec<String>, edges: &mut Vec<NodeEdge>) {
+    for stmt in stmts {
+        match stmt {
+            Stmt::If { cond, then, otherwise } => {
+                conditions.push(cond.to_string());
+                collect_edges(then, conditions, edges);
+                conditions.pop();
+                conditions.push(format!("not {}", cond));
+                collect_edges(otherwise, conditions, edges);
+                conditions.pop();
+            },
+            Stmt::Return(id) => {
+                let label = if conditions.is_empty() {
+                    "return".to_string()
+                } else {
+                    format!("if {}", conditions.join(" and "))
+                };
+                edges.push(NodeEdge::new(*id, label));
+            },
+            Stmt::Set(..) | Stmt::SetTemp(..) | Stmt::Persist(_) | Stmt::Clear(_) | Stmt::Emit(..) => {},
         }
     }
 }
 
+/// A node that runs a small script to pick the next node
+/// starts with the tags `#name`, `#id` and `#fallback` followed by `if`, `set`, `set temp`, `persist`, `clear`, `emit` and `return` statements
 pub struct ScriptNode {
-    tokens: Vec<Token>,
-    tags: Vec<Tag>,
-    fallback: Option<NodeID>,
+    script: Script,
+    program: vm::Program,
 }
 
 impl ScriptNode {
+    /// panics if the script fails to parse; use `parse` to get the error
     pub fn new(script: &str) -> ScriptNode {
-        let mut tokens = Vec::new();
-        let mut tags = Vec::new();
-        let script = script.trim();
-        let script = if script.starts_with('(') {
-            &script[1..script.len()-1]
-        } else { script };
-        let mut words = script.split_whitespace().peekable();
-        let mut open = 0;
-        let mut closed = 0;
-
-        let mut fallback = None;
-
-        while let Some(first) = words.peek() {
-            if first.trim().starts_with("#") {
-                let first = &words.next().unwrap()[1..];
-                match first {
-                    "name" => {
-                        let next = words.next().expect("name to follow #name");
-                        if next.starts_with("\"") {
-                            let mut name = next[1..].to_string();
-                            while !name.ends_with('\"') {
-                                name.push_str(" ");
-                                name.push_str(words.next().expect("name not terminated"));
-                            }
-                            name.pop();
-                            tags.push(Tag::Name(name));
-                        } else {
-                            tags.push(Tag::Name(next.to_string()));
-                        }
-                    },
-                    "id" => {
-                        let next = words.next().expect("id to follow #id");
-                        tags.push(Tag::ID(NodeID::from_str(next)));
-                    },
-                    "fallback" => {
-                        let next = words.next().expect("fallback to follow #fallback");
-                        fallback = Some(NodeID::from_str(next));
-                    },
-                    _ => bevy::log::warn!("unknown tag: #{}", first)
-                }
-            } else {
-                break;
-            }
+        match ScriptNode::parse(script) {
+            Ok(node) => node,
+            Err(e) => panic!("{}", e),
         }
+    }
 
-        while let Some(word) = words.next() {
-            if word.starts_with("Attribute(") {
-                if word.ends_with(')') {
-                    tokens.push(Token::Attribute(Attribute::from_str(word)));
-                } else {
-                    let mut name = word[10..].to_string();
-                    while !name.ends_with(')') {
-                        name.push(' ');
-                        name.push_str(words.next().expect(&format!("{} to have closing ')'", word)));
-                    }
-                    name.pop();
-                    tokens.push(Token::Attribute(Attribute::from_str(&name)));
-                }
-                continue;
-            }
-            if word.starts_with("Index(") {
-                if word.ends_with(')') {
-                    tokens.push(Token::Index(Attribute::from_str(word)));
-                } else {
-                    let mut name = word[6..].to_string();
-                    while !name.ends_with(')') {
-                        name.push(' ');
-                        name.push_str(words.next().expect(&format!("{} to have closing ')'", word)));
-                    }
-                    name.pop();
-                    tokens.push(Token::Attribute(Attribute::new_index(&name)));
-                }
-                continue;
-            }
-            if word.starts_with("NodeID(") {
-                if word.ends_with(')') {
-                    tokens.push(Token::NodeID(NodeID::from_str(word)));
-                } else {
-                    let mut name = word[7..].to_string();
-                    while !name.ends_with(')') {
-                        name.push(' ');
-                        name.push_str(words.next().expect(&format!("{} to have closing ')'", word)));
-                    }
-                    name.pop();
-                    tokens.push(Token::NodeID(NodeID::from_str(&name)));
-                }
-                continue;
-            }
-            if word.starts_with(|c: char| {c.is_digit(10)}) {
-                if word.starts_with("0x") {
-                    tokens.push(Token::Int(usize::from_str_radix(&word[2..], 16).expect("proper hex format")));
-                } if word.contains('.') {
-                    let float: f32 = word.parse().expect("proper float format");
-                    let float = float * 1000.;
-                    tokens.push(Token::Float(float.round() as usize));
-                } else {
-                    tokens.push(Token::Int(word.parse().expect("proper int format")));
-                }
-                continue;
-            }
-            if word.starts_with('"') {
-                let mut string = String::new();
-                let mut escaped = false;
-                for c in word[1..].chars() {
-                    if escaped {
-                        string.push(c);
-                        escaped = false;
-                    } else if c == '\\' {
-                        escaped = true;
-                    } else if c == '"' {
-                        break;
-                    } else {
-                        string.push(c);
-                    }
-                }
-                tokens.push(Token::String(string));
-            }
-            if word.starts_with('[') {
-                let mut hex = Vec::with_capacity(word.len() / 2);
-                let mut digits = word[1..].chars();
-                while let Some(digit) = digits.next() {
-                    if digit == ']' {
-                        break;
-                    }
-                    let digit_two = digits.next().expect("hex string to have even length");
-                    hex.push(u8::from_str_radix(&format!("{}{}", digit, digit_two), 16).expect("proper hex format"));
-                }
-                tokens.push(Token::Raw(hex));
-                continue;
-            }
-            if word.starts_with("Ron(") {
-                let mut ron = String::from(&word[4..]);
-                while ron.matches('(').collect::<Vec<&str>>().len() != ron.matches(')').collect::<Vec<&str>>().len() - 1 {
-                    ron.push_str(words.next().expect("there to be another word"));
-                }
-                if !ron.ends_with(')') {
-                    error!("ron(_) should not have trailing chars");
-                    continue;
-                }
-                #[cfg(not(feature = "ron"))]
-                {
-                    warn!("using ron(_) without ron feature enabled\n");
-                }
-                ron.pop();
-                tokens.push(Token::Ron(ron));
-                continue;
-            }
-            let token = match word {
-                "==" => {Token::Equals},
-                "!=" => {Token::NotEquals},
-                "<=" => {Token::LestThenEq},
-                ">=" => {Token::GratterThenEq},
-                "<" => {Token::LessThen},
-                ">" => {Token::GratterThen},
-                "+" => {Token::Plus},
-                "-" => {Token::Minus},
-                "*" => {Token::Multiply},
-                "/" => {Token::Divide},
-                "(" => {open += 1; Token::OpenParen(open)},
-                ")" => {closed += 1; Token::CloseParen(closed)},
-                "if" => {Token::If},
-                "else" => {Token::Else},
-                "set" => {Token::Set},
-                "none" => {Token::None},
-                "return" => {Token::Return(NodeID::from_str(words.next().expect("NodeID to follow return")))},
-                _ => {Token::Unknown(word.to_string())}
-            };
-            tokens.push(token);
+    /// parses a script, errors have the line and column of the problem
+    pub fn parse(script: &str) -> Result<ScriptNode, BevySpriteAnimationError> {
+        let mut script = script.to_string();
+        let trimmed = script.trim();
+        if trimmed.starts_with('(') && trimmed.ends_with(')') {
+            // blank the brackets so spans still match the text
+            let (open, close) = (script.find('(').unwrap_or_default(), script.rfind(')').unwrap_or_default());
+            script.replace_range(close..=close, " ");
+            script.replace_range(open..=open, " ");
+        }
+        let script = parser::parse(&script)?;
+        Ok(ScriptNode { program: vm::compile(&script), script })
+    }
+
+    /// the script as text, tags on their own lines
+    pub fn source(&self) -> String {
+        let mut data = String::new();
+        if let Some(name) = &self.script.name {
+            data.push_str(&format!("#name {:?}\n", name));
         }
+        if let Some(id) = self.script.id {
+            data.push_str(&format!("#id {}\n", id));
+        }
+        if let Some(fallback) = self.script.fallback {
+            data.push_str(&format!("#fallback {}\n", fallback));
+        }
+        data.push_str(&parser::join(&self.script.body));
+        data
+    }
 
-        ScriptNode { tokens, tags, fallback }
+    /// runs the script by walking its syntax tree instead of its bytecode
+    /// slower than `run`, kept to test and benchmark the bytecode against
+    #[doc(hidden)]
+    pub fn interpret(&self, state: &mut AnimationState) -> NodeResult {
+        self.result(exec(&self.script.body, state))
     }
+
+    fn result(&self, res: Result<Option<NodeID>, String>) -> NodeResult {
+        match res {
+            Ok(Some(id)) => NodeResult::Next(id),
+            Ok(None) => if let Some(fallback) = self.script.fallback {
+                bevy::log::trace!("fallback {:?} used", fallback);
+                NodeResult::Next(fallback)
+            } else {
+                NodeResult::Error("ScriptNode: failed to find a node to return and no fallback was set;\n
+                use #fallback followed by a NodeID at the begging of you script to set a fallback node".to_string())
+            },
+            Err(e) => NodeResult::Error(format!("ScriptNode {}: {}", self.name(), e)),
+        }
+    }
+
     pub fn make_raw<T: serde::Serialize>(value: &T) -> String {
         let data = bincode::serialize(value).expect("val to serialize");
         let mut hex = String::with_capacity(data.len() * 2);
@@ -434,48 +448,286 @@ impl ScriptNode {
     }
 }
 
-fn if_condishion(state: &AnimationState, lhs: &Token, op: &Token, rhs: &Token) -> bool {
-    match (lhs, rhs) {
-        (Token::Index(id), Token::Int(index)) => {
-            let current = state.try_get_attribute::<usize>(id);
-            if current.is_none() {
-                return false;
+/// a value a script expression evaluated to
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    None,
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    Raw(Vec<u8>),
+    Ron(String),
+}
+
+/// runs `stmts` until one returns
+fn exec(stmts: &[Stmt], state: &mut AnimationState) -> Result<Option<NodeID>, String> {
+    for stmt in stmts {
+        match stmt {
+            Stmt::If { cond, then, otherwise } => {
+                let next = if truth(cond, state)? {
+                    exec(then, state)?
+                } else {
+                    exec(otherwise, state)?
+                };
+                if next.is_some() {
+                    return Ok(next);
+                }
+            },
+            Stmt::Set(target, value) => {
+                let value = eval(value, state)?;
+                set(state, *target, value)?
+            },
+            Stmt::SetTemp(target, value) => {
+                let value = eval(value, state)?;
+                set(state, *target, value)?;
+                state.set_temporary(*target);
+            },
+            Stmt::Persist(target) => {state.set_persistent(target);},
+            Stmt::Clear(target) => state.clear_attribute(target),
+            Stmt::Return(id) => return Ok(Some(*id)),
+            Stmt::Emit(name, payload) => state.emit(name, payload.as_deref().unwrap_or("()")),
+        }
+    }
+    Ok(None)
+}
+
+fn eval(expr: &Expr, state: &mut AnimationState) -> Result<Value, String> {
+    Ok(match expr {
+        Expr::None => Value::None,
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Int(i) => Value::Int(*i),
+        Expr::Float(v) => Value::Float(v.0),
+        Expr::String(s) => Value::String(s.clone()),
+        Expr::Raw(bytes) => Value::Raw(bytes.clone()),
+        Expr::Ron(data) => Value::Ron(data.clone()),
+        Expr::Attribute(att) => vm::Slot::of(*att).get(state)?,
+        Expr::Neg(expr) => negate(eval(expr, state)?)?,
+        Expr::Binary(lhs, op, rhs) => arithmetic(eval(lhs, state)?, *op, eval(rhs, state)?)?,
+        Expr::Not(expr) => Value::Bool(!truth(expr, state)?),
+        // the right side is only run when it changes the result
+        Expr::And(lhs, rhs) => Value::Bool(truth(lhs, state)? && truth(rhs, state)?),
+        Expr::Or(lhs, rhs) => Value::Bool(truth(lhs, state)? || truth(rhs, state)?),
+        Expr::Compare(lhs, op, rhs) => {
+            #[cfg(feature = "ron")]
+            if let (Expr::Attribute(att), Expr::Ron(data)) | (Expr::Ron(data), Expr::Attribute(att)) = (&**lhs, &**rhs) {
+                if let Some(res) = compare_ron(state, att, *op, data) {
+                    return res.map(Value::Bool);
+                }
             }
-            let id = current.unwrap();
-            match op {
-            Token::Equals => id == *index,
-            Token::NotEquals => id != *index,
-            Token::LestThenEq => id <= *index,
-            Token::GratterThenEq => id >= *index,
-            Token::LessThen => id < *index,
-            Token::GratterThen => id > *index,
-            _ => panic!("unsupported operator for index")
+            Value::Bool(compare(&eval(lhs, state)?, *op, &eval(rhs, state)?)?)
+        },
+        Expr::Call(func, args) => {
+            let args = args.iter().map(|arg| eval(arg, state)).collect::<Result<Vec<_>, _>>()?;
+            call(*func, &args, state)?
+        },
+        Expr::FramesIn(id) => frames_in(state, *id)?,
+    })
+}
+
+/// compares a custom attribute to a `Ron(..)` literal by reading the literal as the attribute's type
+/// None if the attribute has no type to read the literal as
+#[cfg(feature = "ron")]
+fn compare_ron(state: &AnimationState, att: &Attribute, op: CmpOp, data: &str) -> Option<Result<bool, String>> {
+    if !att.is_custom() || !state.has_data_type(att) {
+        return None;
+    }
+    let equal = match state.try_get_attribute_value(att) {
+        Some(current) => match state.ron_to_value(att, data) {
+            Ok(literal) => *current == literal,
+            Err(e) => return Some(Err(format!("Ron({}) is not the type of {}: {}", data, att, e))),
+        },
+        None => false,
+    };
+    Some(match op {
+        CmpOp::Eq => Ok(equal),
+        CmpOp::Ne => Ok(!equal),
+        _ => Err(format!("can not use {} on {} and Ron({})", op, att, data)),
+    })
+}
+
+/// reads the ron of a primitive as that primitive so it can be compared to literals
+#[cfg(feature = "ron")]
+fn from_ron(data: String) -> Value {
+    if let Ok(b) = data.parse() {
+        Value::Bool(b)
+    } else if let Ok(i) = data.parse() {
+        Value::Int(i)
+    } else if let Ok(v) = data.parse() {
+        Value::Float(v)
+    } else if let Ok(s) = ron::from_str(&data) {
+        Value::String(s)
+    } else {
+        Value::Ron(data)
+    }
+}
+
+#[cfg(feature = "ron")]
+fn to_ron(value: &Value) -> Result<String, String> {
+    Ok(match value {
+        Value::Int(i) => i.to_string(),
+        Value::Float(v) => format!("{:?}", v),
+        Value::Bool(b) => b.to_string(),
+        Value::String(s) => ron::to_string(s).map_err(|e| e.to_string())?,
+        Value::Ron(data) => data.clone(),
+        value => return Err(format!("{:?} can not be written as ron", value)),
+    })
+}
+
+/// ints stay ints, rounding down when divided, and become floats when used with a float
+fn arithmetic(lhs: Value, op: ArithOp, rhs: Value) -> Result<Value, String> {
+    match (&lhs, &rhs) {
+        (Value::Int(a), Value::Int(b)) => {
+            let res = match op {
+                ArithOp::Add => a.checked_add(*b),
+                ArithOp::Sub => a.checked_sub(*b),
+                ArithOp::Mul => a.checked_mul(*b),
+                ArithOp::Div => a.checked_div(*b),
+            };
+            res.map(Value::Int).ok_or_else(|| format!("{} {} {} overflowed or divided by zero", a, op, b))
+        },
+        (Value::Int(_) | Value::Float(_), Value::Int(_) | Value::Float(_)) => {
+            let (a, b) = (as_float(&lhs), as_float(&rhs));
+            Ok(Value::Float(match op {
+                ArithOp::Add => a + b,
+                ArithOp::Sub => a - b,
+                ArithOp::Mul => a * b,
+                ArithOp::Div => a / b,
+            }))
+        },
+        _ => Err(format!("can not use {} on {:?} and {:?}", op, lhs, rhs)),
+    }
+}
+
+fn as_float(value: &Value) -> f64 {
+    match value {
+        Value::Int(i) => *i as f64,
+        Value::Float(v) => *v,
+        _ => f64::NAN,
+    }
+}
+
+fn truth(expr: &Expr, state: &mut AnimationState) -> Result<bool, String> {
+    match eval(expr, state)? {
+        Value::Bool(b) => Ok(b),
+        value => Err(format!("`{}` is {:?} not a bool", expr, value)),
+    }
+}
+
+/// ints stay ints unless used with a float; the parser checks the number of arguments
+fn call(func: Func, args: &[Value], state: &mut AnimationState) -> Result<Value, String> {
+    if let Some(arg) = args.iter().find(|arg| !matches!(arg, Value::Int(_) | Value::Float(_))) {
+        return Err(format!("{} needs numbers, found {:?}", func, arg));
+    }
+    let ints: Option<Vec<i64>> = args.iter().map(|arg| if let Value::Int(i) = arg {Some(*i)} else {None}).collect();
+    let floats: Vec<f64> = args.iter().map(as_float).collect();
+    Ok(match (func, ints.as_deref(), floats.as_slice()) {
+        // rand is inclusive for ints so `rand(1, 3)` can be 3
+        (Func::Rand, Some(&[a, b]), _) => {
+            if b < a {
+                return Err(format!("rand({}, {}) has a larger start than end", a, b));
             }
-        }
-        (Token::Index(id), Token::Index(id2)) => {
-            let id = state.try_get_attribute::<usize>(id);
-            let id2 = state.try_get_attribute::<usize>(id2);
-            if id.is_none() || id2.is_none() {
-                return false;
+            let range = (b as i128 - a as i128 + 1) as u128;
+            Value::Int((a as i128 + (state.random() as u128 % range) as i128) as i64)
+        },
+        (Func::Rand, _, &[a, b]) => {
+            if a > b || a.is_nan() || b.is_nan() {
+                return Err(format!("rand({:?}, {:?}) has a larger start than end", a, b));
             }
-            match op {
-            Token::Equals => id == id2,
-            Token::NotEquals => id != id2,
-            Token::LestThenEq => id <= id2,
-            Token::GratterThenEq => id >= id2,
-            Token::LessThen => id < id2,
-            Token::GratterThen => id > id2,
-            _ => panic!("unsupported operator for index")
+            Value::Float(a + unit(state) * (b - a))
+        },
+        (Func::Chance, _, &[p]) => Value::Bool(unit(state) < p),
+        (Func::Min, Some(ints), _) => Value::Int(ints.iter().copied().min().unwrap_or_default()),
+        (Func::Min, None, floats) => Value::Float(floats.iter().copied().fold(f64::INFINITY, f64::min)),
+        (Func::Max, Some(ints), _) => Value::Int(ints.iter().copied().max().unwrap_or_default()),
+        (Func::Max, None, floats) => Value::Float(floats.iter().copied().fold(f64::NEG_INFINITY, f64::max)),
+        (Func::Abs, Some(&[i]), _) => Value::Int(i.checked_abs().ok_or_else(|| format!("abs({}) overflowed", i))?),
+        (Func::Abs, _, &[v]) => Value::Float(v.abs()),
+        (Func::Clamp, Some(&[v, low, high]), _) if low <= high => Value::Int(v.clamp(low, high)),
+        (Func::Clamp, _, &[v, low, high]) if low <= high => Value::Float(v.clamp(low, high)),
+        (func, _, _) => return Err(format!("can not call {} with {:?}", func, args)),
+    })
+}
+
+/// a random float from 0 up to but not including 1
+fn unit(state: &mut AnimationState) -> f64 {
+    (state.random() >> 11) as f64 / (1u64 << 53) as f64
+}
+
+fn frames_in(state: &AnimationState, id: NodeID) -> Result<Value, String> {
+    match state.frame_count(id) {
+        Some(frames) => Ok(Value::Int(frames as i64)),
+        None => Err(format!("frames_in({}) is not a node with frames in the tree", id)),
+    }
+}
+
+fn negate(value: Value) -> Result<Value, String> {
+    match value {
+        Value::Int(i) => Ok(Value::Int(-i)),
+        Value::Float(v) => Ok(Value::Float(-v)),
+        value => Err(format!("can not negate {:?}", value)),
+    }
+}
+
+fn compare(lhs: &Value, op: CmpOp, rhs: &Value) -> Result<bool, String> {
+    use std::cmp::Ordering;
+    let ordering = match (lhs, rhs) {
+        (Value::None, _) | (_, Value::None) => return Ok(match op {
+            CmpOp::Eq => lhs == rhs,
+            CmpOp::Ne => lhs != rhs,
+            _ => false,
+        }),
+        (Value::Int(a), Value::Int(b)) => Some(a.cmp(b)),
+        (Value::Int(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Int(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::String(a), Value::String(b)) => Some(a.cmp(b)),
+        (Value::Bool(_), Value::Bool(_)) | (Value::Raw(_), Value::Raw(_)) | (Value::Ron(_), Value::Ron(_)) => match op {
+            CmpOp::Eq => return Ok(lhs == rhs),
+            CmpOp::Ne => return Ok(lhs != rhs),
+            _ => return Err(format!("can not use {} on {:?} and {:?}", op, lhs, rhs)),
+        },
+        _ => return Err(format!("can not compare {:?} {} {:?}", lhs, op, rhs)),
+    };
+    let Some(ordering) = ordering else {return Ok(false);};
+    Ok(match op {
+        CmpOp::Eq => ordering == Ordering::Equal,
+        CmpOp::Ne => ordering != Ordering::Equal,
+        CmpOp::Lt => ordering == Ordering::Less,
+        CmpOp::Le => ordering != Ordering::Greater,
+        CmpOp::Gt => ordering == Ordering::Greater,
+        CmpOp::Ge => ordering != Ordering::Less,
+    })
+}
+
+fn set(state: &mut AnimationState, target: Attribute, value: Value) -> Result<(), String> {
+    match value {
+        Value::Int(i) if target.is_index() || target == Attribute::FRAMES => {
+            let i = usize::try_from(i).map_err(|_| format!("can not set {} to {}", target, i))?;
+            state.set_attribute(target, i);
+        },
+        Value::Float(v) if target == Attribute::DELTA || target == Attribute::TIME_ON_FRAME => state.set_attribute(target, v as f32),
+        Value::Float(v) if target.is_index() || target == Attribute::FRAMES => {
+            if !(v >= 0. && v <= usize::MAX as f64) {
+                return Err(format!("can not set {} to {}", target, v));
             }
+            state.set_attribute(target, v as usize);
         },
-        (Token::Index(id), Token::None) => match op {
-            Token::Equals => state.try_get_attribute::<usize>(id).is_none(),
-            Token::NotEquals => state.try_get_attribute::<usize>(id).is_some(),
-            _ => panic!("unsupported operator for index")
-        }
-        (Token::Index(_), _) => {panic!("unsupported operator for index")},
-        (_, _) => todo!("unsupported")
+        Value::Bool(b) if target == Attribute::FLIP_X || target == Attribute::FLIP_Y => state.set_attribute(target, b),
+        #[cfg(feature = "ron")]
+        value @ (Value::Int(_) | Value::Float(_) | Value::Bool(_) | Value::String(_)) if target.is_custom() && state.has_data_type(&target) => {
+            state.set_attribute_from_ron(target, &to_ron(&value)?).map_err(|e| e.to_string())?
+        },
+        Value::Raw(bytes) => match state.set_attribute_raw(target, &bytes) {
+            Ok(()) => {},
+            Err(BevySpriteAnimationError::AttributeNotFound(_)) => return Err(format!("can not set raw data of {} before it is set", target)),
+            Err(e) => return Err(format!("can not set {} to raw data: {}", target, e)),
+        },
+        #[cfg(feature = "ron")]
+        Value::Ron(data) => state.set_attribute_from_ron(target, &data).map_err(|e| e.to_string())?,
+        value => return Err(format!("can not set {} to {:?}", target, value)),
     }
+    Ok(())
 }
 
 #[cfg(feature = "serialize")]
@@ -495,21 +747,23 @@ mod serialize {
     impl NodeLoader for ScriptNodeLoader {
         fn load(&mut self, data: &str, _: &AssetServer) -> Result<Box<dyn AnimationNode>, Error> {
             let data = data.trim();
-            let data = if data.starts_with("ScriptNode(") {
-                if data.ends_with("),") {
-                    &data[data.find("ScriptNode(").unwrap() + 11..data.len() - 2]
+            let data = if let Some(start) = data.find("ScriptNode(").filter(|_| data.starts_with("ScriptNode(")) {
+                let end = if data.ends_with("),") {
+                    data.len() - 2
                 } else if data.ends_with(')') {
-                    &data[data.find("ScriptNode(").unwrap() + 11..data.len() - 1]
+                    data.len() - 1
                 } else {
-                    return Err(Error::MalformedStr { message: format!("Found ScriptNode( but failed to find Closing ')'"), location: crate::here!() })
-                }
+                    return Err(Error::MalformedStr { message: "Found ScriptNode( but failed to find Closing ')'".to_string(), location: crate::here!() })
+                };
+                // keep the columns of the first line the same as the file
+                format!("{}{}", " ".repeat(start + 11), &data[start + 11..end])
             } else {
-                &data
+                data.to_string()
             };
-            Ok(Box::new(ScriptNode::new(data)))
+            Ok(Box::new(ScriptNode::parse(&data)?))
         }
         fn can_load(&self) -> &[&str] {
             &["ScriptNode"]
         }
     }
-}
\ No newline at end of file
+}
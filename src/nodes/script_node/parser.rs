@@ -0,0 +1,869 @@
+use crate::error::BevySpriteAnimationError as Error;
+use crate::prelude::{Attribute, NodeID};
+
+#[cfg(test)]
+mod test {
+    use crate::test::with_indices;
+    use super::*;
+
+    #[test]
+    fn tokens_have_spans() {
+        let tokens = tokenize("if Index(Stand) >= 6\n  return NodeID(0x1)").unwrap();
+        assert_eq!(tokens[0], (Tok::Word("if".to_string()), Span { line: 1, column: 1 }));
+        assert_eq!(tokens[1], (Tok::Wrapped("Index".to_string(), "Stand".to_string()), Span { line: 1, column: 4 }));
+        assert_eq!(tokens[2], (Tok::Symbol(">="), Span { line: 1, column: 17 }));
+        assert_eq!(tokens[4], (Tok::Word("return".to_string()), Span { line: 2, column: 3 }));
+    }
+
+    #[test]
+    fn errors_have_spans() {
+        let Err(Error::ScriptError { line, column, .. }) = parse("set Index(Stand) 1\nset Attribute(Oops 2") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (2, 5));
+        let Err(Error::ScriptError { line, column, .. }) = parse("return NodeID(0x1)\nif Index(Stand) >=") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (2, 19));
+        let Err(Error::ScriptError { line, column, .. }) = parse("#id NodeID(0xZZ)") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (1, 5));
+    }
+
+    #[test]
+    fn blocks() {
+        let source = format!("if Index(Stand) >= 6 {{ set Index(Fall) = 1 set Index(Stand) = 0 }} else if Index(Stand) == 0 {{ return {} }} else {{ if Index(Fall) == 2 {{ return {} }} set Index(Fall) = 2 }}", NodeID::from_u64(1), NodeID::from_u64(2));
+        let script = parse(&source).unwrap();
+        assert_eq!(with_indices(&["Stand", "Fall"], || join(&script.body)), source);
+        let Stmt::If { then, otherwise, .. } = &script.body[0] else {panic!("expected if")};
+        assert_eq!(then.len(), 2);
+        let [Stmt::If { otherwise, .. }] = otherwise.as_slice() else {panic!("expected else if")};
+        assert_eq!(otherwise.len(), 2);
+        let Err(Error::ScriptError { line, column, .. }) = parse("if 1 == 1 {\n set Index(Fall) = 1") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (1, 11));
+    }
+
+    #[test]
+    fn calls() {
+        let source = format!("if chance(0.25) and rand(1, frames_in({})) > max(1, -abs(Index(Stand)), clamp(2.5, 0, 1)) {{ return {} }}", NodeID::from_u64(1), NodeID::from_u64(2));
+        let script = parse(&source).unwrap();
+        assert_eq!(with_indices(&["Stand"], || join(&script.body)), source);
+        let Err(Error::ScriptError { message, line, column }) = parse("set Index(Fall) = clamp(1, 2)") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (1, 19));
+        assert!(message.contains("takes 3 arguments"), "{}", message);
+        let Err(Error::ScriptError { line, column, .. }) = parse("set Index(Fall) = min(1, 2") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (1, 27));
+    }
+
+    #[test]
+    fn temporary() {
+        let source = "set temp Index(Landed) = 1 if Index(Stand) == 0 { persist Index(Landed) } else { clear Index(Landed) }";
+        let script = parse(source).unwrap();
+        assert_eq!(with_indices(&["Stand", "Landed"], || join(&script.body)), source);
+        assert!(matches!(script.body[0], Stmt::SetTemp(..)));
+        let Err(Error::ScriptError { line, column, .. }) = parse("clear 1") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (1, 7));
+    }
+
+    #[test]
+    fn emit() {
+        let script = parse("emit attack_landed emit hit((damage: 5, at: \"head (left)\")) emit Index()").unwrap();
+        assert_eq!(script.body, vec![
+            Stmt::Emit("attack_landed".to_string(), None),
+            Stmt::Emit("hit".to_string(), Some("(damage: 5, at: \"head (left)\")".to_string())),
+            Stmt::Emit("Index".to_string(), None),
+        ]);
+        assert_eq!(parse(&join(&script.body)).unwrap(), script);
+        let Err(Error::ScriptError { line, column, .. }) = parse("emit 5") else {panic!("expected a ScriptError")};
+        assert_eq!((line, column), (1, 6));
+    }
+
+    #[test]
+    fn precedence() {
+        let script = parse("set Index(Fall) = 6 - Index(Stand) * 2 + (1 - 2) - -3").unwrap();
+        let Stmt::Set(_, value) = &script.body[0] else {panic!("expected set")};
+        assert_eq!(with_indices(&["Stand"], || value.to_string()), "6 - Index(Stand) * 2 + (1 - 2) - -3");
+        let Expr::Binary(lhs, ArithOp::Sub, _) = value else {panic!("expected `-` at the top")};
+        assert!(matches!(**lhs, Expr::Binary(_, ArithOp::Add, _)));
+        let script = parse("if 1 + 2 * 3 >= (1 + 2) * 3 return NodeID(0x1)").unwrap();
+        assert_eq!(parse(&join(&script.body)).unwrap(), script);
+    }
+}
+
+/// where a token starts in a script, both counted from 1
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn error(self, message: impl Into<String>) -> Error {
+        Error::ScriptError { message: message.into(), line: self.line, column: self.column }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Tok {
+    /// `#name`, `#id` or `#fallback` without the `#`
+    Tag(String),
+    Word(String),
+    /// `Attribute(..)`, `Index(..)`, `Core(..)`, `NodeID(..)` or `Ron(..)` with the text between the brackets
+    Wrapped(String, String),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Raw(Vec<u8>),
+    Symbol(&'static str),
+}
+
+impl std::fmt::Display for Tok {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Tok::Tag(tag) => write!(f, "#{}", tag),
+            Tok::Word(word) => write!(f, "`{}`", word),
+            Tok::Wrapped(kind, inner) => write!(f, "`{}({})`", kind, inner),
+            Tok::Int(i) => write!(f, "{}", i),
+            Tok::Float(v) => write!(f, "{:?}", v),
+            Tok::Str(s) => write!(f, "{:?}", s),
+            Tok::Raw(_) => f.write_str("raw data"),
+            Tok::Symbol(s) => write!(f, "`{}`", s),
+        }
+    }
+}
+
+const WRAPPERS: [&str; 5] = ["Attribute", "Index", "Core", "NodeID", "Ron"];
+/// longest first so `>=` is not read as `>` `=`
+const SYMBOLS: [&str; 16] = ["==", "!=", "<=", ">=", "<", ">", "=", "+", "-", "*", "/", "(", ")", "{", "}", ","];
+
+struct Lexer {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    column: usize,
+}
+
+impl Lexer {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+        Some(c)
+    }
+
+    fn span(&self) -> Span {
+        Span { line: self.line, column: self.column }
+    }
+
+    fn take_while(&mut self, f: impl Fn(char) -> bool) -> String {
+        let mut res = String::new();
+        while let Some(c) = self.peek() {
+            if !f(c) {
+                break;
+            }
+            res.push(c);
+            self.bump();
+        }
+        res
+    }
+
+    fn number(&mut self, span: Span) -> Result<Tok, Error> {
+        if self.peek() == Some('0') && matches!(self.peek_at(1), Some('x' | 'X' | 'b' | 'B' | 'o' | 'O')) {
+            self.bump();
+            let radix = match self.bump() {
+                Some('x' | 'X') => 16,
+                Some('b' | 'B') => 2,
+                _ => 8,
+            };
+            let digits = self.take_while(|c| c.is_ascii_alphanumeric());
+            return i64::from_str_radix(&digits, radix).map(Tok::Int)
+                .map_err(|e| span.error(format!("invalid number `{}`: {}", digits, e)));
+        }
+        let mut digits = self.take_while(|c| c.is_ascii_digit());
+        if self.peek() == Some('.') && self.peek_at(1).is_some_and(|c| c.is_ascii_digit()) {
+            self.bump();
+            digits.push('.');
+            digits.push_str(&self.take_while(|c| c.is_ascii_digit()));
+            return digits.parse().map(Tok::Float)
+                .map_err(|e| span.error(format!("invalid number `{}`: {}", digits, e)));
+        }
+        if self.peek().is_some_and(|c| c.is_alphabetic()) {
+            return Err(self.span().error(format!("unexpected `{}` after number", self.peek().unwrap_or_default())));
+        }
+        digits.parse().map(Tok::Int)
+            .map_err(|e| span.error(format!("invalid number `{}`: {}", digits, e)))
+    }
+
+    fn string(&mut self, span: Span) -> Result<Tok, Error> {
+        self.bump();
+        let mut res = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(span.error("string is never closed")),
+                Some('"') => return Ok(Tok::Str(res)),
+                Some('\\') => match self.bump() {
+                    Some('n') => res.push('\n'),
+                    Some('t') => res.push('\t'),
+                    Some(c) => res.push(c),
+                    None => return Err(span.error("string is never closed")),
+                },
+                Some(c) => res.push(c),
+            }
+        }
+    }
+
+    fn raw(&mut self, span: Span) -> Result<Tok, Error> {
+        self.bump();
+        let digits = self.take_while(|c| c != ']');
+        if self.bump() != Some(']') {
+            return Err(span.error("`[` is never closed"));
+        }
+        if digits.len() & 1 == 1 {
+            return Err(span.error("raw data must have two hex digits per byte"));
+        }
+        let mut bytes = Vec::with_capacity(digits.len() / 2);
+        for i in (0..digits.len()).step_by(2) {
+            let byte = digits.get(i..i + 2).and_then(|byte| u8::from_str_radix(byte, 16).ok())
+                .ok_or_else(|| span.error(format!("invalid hex in raw data `{}`", digits)))?;
+            bytes.push(byte);
+        }
+        Ok(Tok::Raw(bytes))
+    }
+
+    /// the text up to the `)` that closes the `(` at the current position
+    fn wrapped(&mut self, kind: String, span: Span) -> Result<Tok, Error> {
+        self.bump();
+        let mut depth = 0;
+        let mut in_string = false;
+        let mut inner = String::new();
+        loop {
+            let Some(c) = self.bump() else {
+                return Err(span.error(format!("`{}(` is never closed", kind)));
+            };
+            match c {
+                '"' => in_string = !in_string,
+                '\\' if in_string => {
+                    inner.push(c);
+                    if let Some(c) = self.bump() {
+                        inner.push(c);
+                    }
+                    continue;
+                },
+                '(' if !in_string => depth += 1,
+                ')' if !in_string && depth == 0 => break,
+                ')' if !in_string => depth -= 1,
+                _ => {},
+            }
+            inner.push(c);
+        }
+        Ok(Tok::Wrapped(kind, inner.trim().to_string()))
+    }
+}
+
+pub(super) fn tokenize(script: &str) -> Result<Vec<(Tok, Span)>, Error> {
+    let mut lexer = Lexer { chars: script.chars().collect(), pos: 0, line: 1, column: 1 };
+    let mut tokens = Vec::new();
+    while let Some(c) = lexer.peek() {
+        if c.is_whitespace() {
+            lexer.bump();
+            continue;
+        }
+        let span = lexer.span();
+        let token = if c == '#' {
+            lexer.bump();
+            let tag = lexer.take_while(|c| c.is_alphanumeric() || c == '_');
+            if tag.is_empty() {
+                return Err(span.error("expected a tag name after `#`"));
+            }
+            Tok::Tag(tag)
+        } else if c.is_ascii_digit() {
+            lexer.number(span)?
+        } else if c == '"' {
+            lexer.string(span)?
+        } else if c == '[' {
+            lexer.raw(span)?
+        } else if c.is_alphabetic() || c == '_' {
+            let word = lexer.take_while(|c| c.is_alphanumeric() || c == '_');
+            // the payload of `emit name(..)` is ron so is kept as text like `Ron(..)`
+            let emitted = matches!(tokens.last(), Some((Tok::Word(last), _)) if last == "emit");
+            if (WRAPPERS.contains(&word.as_str()) || emitted) && lexer.peek() == Some('(') {
+                lexer.wrapped(word, span)?
+            } else {
+                Tok::Word(word)
+            }
+        } else {
+            let symbol = SYMBOLS.iter().find(|symbol| {
+                symbol.chars().enumerate().all(|(i, s)| lexer.peek_at(i) == Some(s))
+            }).ok_or_else(|| span.error(format!("unexpected character `{}`", c)))?;
+            for _ in 0..symbol.len() {
+                lexer.bump();
+            }
+            Tok::Symbol(symbol)
+        };
+        tokens.push((token, span));
+    }
+    Ok(tokens)
+}
+
+/// a float that can be hashed and compared by its bits so scripts can be hashed
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Float(pub f64);
+
+impl PartialEq for Float {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.to_bits() == other.0.to_bits()
+    }
+}
+
+impl Eq for Float {}
+
+impl std::hash::Hash for Float {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.0.to_bits().hash(state);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum CmpOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl CmpOp {
+    fn from_symbol(symbol: &str) -> Option<CmpOp> {
+        Some(match symbol {
+            "==" => CmpOp::Eq,
+            "!=" => CmpOp::Ne,
+            "<" => CmpOp::Lt,
+            "<=" => CmpOp::Le,
+            ">" => CmpOp::Gt,
+            ">=" => CmpOp::Ge,
+            _ => return None,
+        })
+    }
+}
+
+impl std::fmt::Display for CmpOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            CmpOp::Eq => "==",
+            CmpOp::Ne => "!=",
+            CmpOp::Lt => "<",
+            CmpOp::Le => "<=",
+            CmpOp::Gt => ">",
+            CmpOp::Ge => ">=",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum ArithOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl ArithOp {
+    fn from_symbol(symbol: &str) -> Option<ArithOp> {
+        Some(match symbol {
+            "+" => ArithOp::Add,
+            "-" => ArithOp::Sub,
+            "*" => ArithOp::Mul,
+            "/" => ArithOp::Div,
+            _ => return None,
+        })
+    }
+
+    fn precedence(self) -> u8 {
+        match self {
+            ArithOp::Add | ArithOp::Sub => 4,
+            ArithOp::Mul | ArithOp::Div => 5,
+        }
+    }
+}
+
+impl std::fmt::Display for ArithOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            ArithOp::Add => "+",
+            ArithOp::Sub => "-",
+            ArithOp::Mul => "*",
+            ArithOp::Div => "/",
+        })
+    }
+}
+
+/// the functions scripts can call; `frames_in` is `Expr::FramesIn` as it takes a `NodeID`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(super) enum Func {
+    Rand,
+    Chance,
+    Min,
+    Max,
+    Abs,
+    Clamp,
+}
+
+impl Func {
+    fn from_name(name: &str) -> Option<Func> {
+        Some(match name {
+            "rand" => Func::Rand,
+            "chance" => Func::Chance,
+            "min" => Func::Min,
+            "max" => Func::Max,
+            "abs" => Func::Abs,
+            "clamp" => Func::Clamp,
+            _ => return None,
+        })
+    }
+
+    /// the fewest and most arguments the function takes
+    fn arguments(self) -> (usize, usize) {
+        match self {
+            Func::Rand => (2, 2),
+            Func::Chance | Func::Abs => (1, 1),
+            Func::Min | Func::Max => (2, usize::MAX),
+            Func::Clamp => (3, 3),
+        }
+    }
+}
+
+impl std::fmt::Display for Func {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Func::Rand => "rand",
+            Func::Chance => "chance",
+            Func::Min => "min",
+            Func::Max => "max",
+            Func::Abs => "abs",
+            Func::Clamp => "clamp",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Expr {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(Float),
+    String(String),
+    Raw(Vec<u8>),
+    Ron(String),
+    Attribute(Attribute),
+    Neg(Box<Expr>),
+    Binary(Box<Expr>, ArithOp, Box<Expr>),
+    Compare(Box<Expr>, CmpOp, Box<Expr>),
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Call(Func, Vec<Expr>),
+    FramesIn(NodeID),
+}
+
+impl Expr {
+    /// how tightly the expression binds, used to only add the brackets that are needed when printing
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Or(..) => 0,
+            Expr::And(..) => 1,
+            Expr::Not(..) => 2,
+            Expr::Compare(..) => 3,
+            Expr::Binary(_, op, _) => op.precedence(),
+            _ => 6,
+        }
+    }
+}
+
+/// writes `expr` in brackets if it binds less tightly than `precedence`
+fn fmt_operand(f: &mut std::fmt::Formatter<'_>, expr: &Expr, precedence: u8) -> std::fmt::Result {
+    if expr.precedence() < precedence {
+        write!(f, "({})", expr)
+    } else {
+        write!(f, "{}", expr)
+    }
+}
+
+impl std::fmt::Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::None => f.write_str("none"),
+            Expr::Bool(b) => write!(f, "{}", b),
+            Expr::Int(i) => write!(f, "{}", i),
+            Expr::Float(v) => write!(f, "{:?}", v.0),
+            Expr::String(s) => write!(f, "{:?}", s),
+            Expr::Raw(bytes) => {
+                f.write_str("[")?;
+                for byte in bytes {
+                    write!(f, "{:02X}", byte)?;
+                }
+                f.write_str("]")
+            },
+            Expr::Ron(data) => write!(f, "Ron({})", data),
+            Expr::Attribute(att) => write!(f, "{}", att),
+            Expr::Neg(expr) => {
+                f.write_str("-")?;
+                fmt_operand(f, expr, 6)
+            },
+            Expr::Binary(lhs, op, rhs) => {
+                // the right side needs brackets at the same precedence as `-` and `/` are not associative
+                fmt_operand(f, lhs, op.precedence())?;
+                write!(f, " {} ", op)?;
+                fmt_operand(f, rhs, op.precedence() + 1)
+            },
+            Expr::Compare(lhs, op, rhs) => {
+                fmt_operand(f, lhs, 4)?;
+                write!(f, " {} ", op)?;
+                fmt_operand(f, rhs, 4)
+            },
+            Expr::Not(expr) => {
+                f.write_str("not ")?;
+                fmt_operand(f, expr, 2)
+            },
+            Expr::And(lhs, rhs) => {
+                fmt_operand(f, lhs, 1)?;
+                f.write_str(" and ")?;
+                fmt_operand(f, rhs, 2)
+            },
+            Expr::Or(lhs, rhs) => {
+                fmt_operand(f, lhs, 0)?;
+                f.write_str(" or ")?;
+                fmt_operand(f, rhs, 1)
+            },
+            Expr::Call(func, args) => {
+                let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+                write!(f, "{}({})", func, args.join(", "))
+            },
+            Expr::FramesIn(id) => write!(f, "frames_in({})", id),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(super) enum Stmt {
+    If {
+        cond: Expr,
+        then: Vec<Stmt>,
+        otherwise: Vec<Stmt>,
+    },
+    Set(Attribute, Expr),
+    /// sets the attribute and makes it temporary so it is cleared after an update it is not set in
+    SetTemp(Attribute, Expr),
+    Persist(Attribute),
+    Clear(Attribute),
+    Return(NodeID),
+    /// the name of a `ScriptEvent` and its ron payload
+    Emit(String, Option<String>),
+}
+
+impl std::fmt::Display for Stmt {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Stmt::If { cond, then, otherwise } => {
+                write!(f, "if {} {{ {} }}", cond, join(then))?;
+                match otherwise.as_slice() {
+                    [] => Ok(()),
+                    [Stmt::If { .. }] => write!(f, " else {}", otherwise[0]),
+                    _ => write!(f, " else {{ {} }}", join(otherwise)),
+                }
+            },
+            Stmt::Set(target, value) => write!(f, "set {} = {}", target, value),
+            Stmt::SetTemp(target, value) => write!(f, "set temp {} = {}", target, value),
+            Stmt::Persist(target) => write!(f, "persist {}", target),
+            Stmt::Clear(target) => write!(f, "clear {}", target),
+            Stmt::Return(id) => write!(f, "return {}", id),
+            Stmt::Emit(name, None) => write!(f, "emit {}", name),
+            Stmt::Emit(name, Some(payload)) => write!(f, "emit {}({})", name, payload),
+        }
+    }
+}
+
+pub(super) fn join(stmts: &[Stmt]) -> String {
+    stmts.iter().map(|stmt| stmt.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash)]
+pub(super) struct Script {
+    pub name: Option<String>,
+    pub id: Option<NodeID>,
+    pub fallback: Option<NodeID>,
+    pub body: Vec<Stmt>,
+}
+
+struct Parser {
+    tokens: Vec<(Tok, Span)>,
+    pos: usize,
+    end: Span,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Tok> {
+        self.tokens.get(self.pos).map(|(tok, _)| tok)
+    }
+
+    fn next(&mut self, expected: &str) -> Result<(Tok, Span), Error> {
+        let token = self.tokens.get(self.pos).cloned()
+            .ok_or_else(|| self.end.error(format!("expected {} but the script ended", expected)))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_word(&self, word: &str) -> bool {
+        matches!(self.peek(), Some(Tok::Word(w)) if w == word)
+    }
+
+    fn script(&mut self) -> Result<Script, Error> {
+        let mut script = Script::default();
+        while let Some(Tok::Tag(_)) = self.peek() {
+            let (Tok::Tag(tag), span) = self.next("a tag")? else {unreachable!()};
+            match tag.as_str() {
+                "name" => script.name = Some(match self.next("a name after #name")? {
+                    (Tok::Str(name) | Tok::Word(name), _) => name,
+                    (tok, span) => return Err(span.error(format!("expected a name after #name, found {}", tok))),
+                }),
+                "id" => script.id = Some(self.node_id()?),
+                "fallback" => script.fallback = Some(self.node_id()?),
+                _ => return Err(span.error(format!("unknown tag #{}", tag))),
+            }
+        }
+        while self.peek().is_some() {
+            script.body.push(self.statement()?);
+        }
+        Ok(script)
+    }
+
+    fn statement(&mut self) -> Result<Stmt, Error> {
+        match self.next("a statement")? {
+            (Tok::Word(word), _) if word == "if" => {
+                let cond = self.expression()?;
+                let then = self.block()?;
+                let otherwise = if self.is_word("else") {
+                    self.pos += 1;
+                    self.block()?
+                } else {
+                    Vec::new()
+                };
+                Ok(Stmt::If { cond, then, otherwise })
+            },
+            (Tok::Word(word), _) if word == "set" => {
+                let temp = self.is_word("temp");
+                if temp {
+                    self.pos += 1;
+                }
+                let target = self.attribute()?;
+                if self.peek() == Some(&Tok::Symbol("=")) {
+                    self.pos += 1;
+                }
+                let value = self.expression()?;
+                Ok(if temp {Stmt::SetTemp(target, value)} else {Stmt::Set(target, value)})
+            },
+            (Tok::Word(word), _) if word == "persist" => Ok(Stmt::Persist(self.attribute()?)),
+            (Tok::Word(word), _) if word == "clear" => Ok(Stmt::Clear(self.attribute()?)),
+            (Tok::Word(word), _) if word == "return" => Ok(Stmt::Return(self.node_id()?)),
+            (Tok::Word(word), _) if word == "emit" => match self.next("an event name")? {
+                (Tok::Word(name), _) => Ok(Stmt::Emit(name, None)),
+                (Tok::Wrapped(name, payload), _) if payload.is_empty() => Ok(Stmt::Emit(name, None)),
+                (Tok::Wrapped(name, payload), span) => {
+                    #[cfg(feature = "ron")]
+                    if let Err(e) = ron::from_str::<ron::Value>(&payload) {
+                        return Err(span.error(format!("invalid ron in the payload of `{}`: {}", name, e)));
+                    }
+                    Ok(Stmt::Emit(name, Some(payload)))
+                },
+                (tok, span) => Err(span.error(format!("expected an event name after `emit`, found {}", tok))),
+            },
+            (tok, span) => Err(span.error(format!("expected `if`, `set`, `persist`, `clear`, `emit` or `return`, found {}", tok))),
+        }
+    }
+
+    /// statements in `{ }` or a single statement
+    fn block(&mut self) -> Result<Vec<Stmt>, Error> {
+        if self.peek() != Some(&Tok::Symbol("{")) {
+            return Ok(vec![self.statement()?]);
+        }
+        let (_, open) = self.next("`{`")?;
+        let mut stmts = Vec::new();
+        loop {
+            match self.peek() {
+                Some(Tok::Symbol("}")) => {
+                    self.pos += 1;
+                    return Ok(stmts);
+                },
+                Some(_) => stmts.push(self.statement()?),
+                None => return Err(open.error("`{` is never closed")),
+            }
+        }
+    }
+
+    /// `or` binds the loosest then `and`, `not`, comparisons and arithmetic
+    fn expression(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.and()?;
+        while self.is_word("or") {
+            self.pos += 1;
+            lhs = Expr::Or(Box::new(lhs), Box::new(self.and()?));
+        }
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, Error> {
+        let mut lhs = self.not()?;
+        while self.is_word("and") {
+            self.pos += 1;
+            lhs = Expr::And(Box::new(lhs), Box::new(self.not()?));
+        }
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, Error> {
+        if self.is_word("not") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.not()?)));
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, Error> {
+        let lhs = self.arithmetic(4)?;
+        if let Some(Tok::Symbol(symbol)) = self.peek() {
+            if let Some(op) = CmpOp::from_symbol(symbol) {
+                self.pos += 1;
+                let rhs = self.arithmetic(4)?;
+                return Ok(Expr::Compare(Box::new(lhs), op, Box::new(rhs)));
+            }
+        }
+        Ok(lhs)
+    }
+
+    /// operators that bind at least as tightly as `precedence`, left to right
+    fn arithmetic(&mut self, precedence: u8) -> Result<Expr, Error> {
+        let mut lhs = self.unary()?;
+        while let Some(Tok::Symbol(symbol)) = self.peek() {
+            let Some(op) = ArithOp::from_symbol(symbol).filter(|op| op.precedence() >= precedence) else {break;};
+            self.pos += 1;
+            let rhs = self.arithmetic(op.precedence() + 1)?;
+            lhs = Expr::Binary(Box::new(lhs), op, Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, Error> {
+        if self.peek() == Some(&Tok::Symbol("-")) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, Error> {
+        match self.next("a value")? {
+            (Tok::Symbol("("), span) => {
+                let expr = self.expression()?;
+                match self.next("`)`")? {
+                    (Tok::Symbol(")"), _) => Ok(expr),
+                    (tok, _) => Err(span.error(format!("`(` is never closed, found {}", tok))),
+                }
+            },
+            (Tok::Int(i), _) => Ok(Expr::Int(i)),
+            (Tok::Float(v), _) => Ok(Expr::Float(Float(v))),
+            (Tok::Str(s), _) => Ok(Expr::String(s)),
+            (Tok::Raw(bytes), _) => Ok(Expr::Raw(bytes)),
+            (Tok::Word(word), _) if word == "none" => Ok(Expr::None),
+            (Tok::Word(word), _) if word == "true" => Ok(Expr::Bool(true)),
+            (Tok::Word(word), _) if word == "false" => Ok(Expr::Bool(false)),
+            (Tok::Word(word), span) if word == "frames_in" => {
+                self.open(&word, span)?;
+                let id = self.node_id()?;
+                self.close(&word, span)?;
+                Ok(Expr::FramesIn(id))
+            },
+            (Tok::Wrapped(kind, inner), _) if kind == "Ron" => Ok(Expr::Ron(inner)),
+            (Tok::Wrapped(kind, inner), span) if kind != "NodeID" => Ok(Expr::Attribute(to_attribute(&kind, &inner, span)?)),
+            (Tok::Word(word), span) => match Func::from_name(&word) {
+                Some(func) => self.call(func, span),
+                None => Err(span.error(format!("expected a value, found {}", Tok::Word(word)))),
+            },
+            (tok, span) => Err(span.error(format!("expected a value, found {}", tok))),
+        }
+    }
+
+    /// the arguments of `func` in brackets
+    fn call(&mut self, func: Func, span: Span) -> Result<Expr, Error> {
+        let name = func.to_string();
+        self.open(&name, span)?;
+        let mut args = vec![self.expression()?];
+        while self.peek() == Some(&Tok::Symbol(",")) {
+            self.pos += 1;
+            args.push(self.expression()?);
+        }
+        self.close(&name, span)?;
+        let (least, most) = func.arguments();
+        if args.len() < least || args.len() > most {
+            let expected = if least == most {least.to_string()} else {format!("at least {}", least)};
+            return Err(span.error(format!("`{}` takes {} arguments, found {}", func, expected, args.len())));
+        }
+        Ok(Expr::Call(func, args))
+    }
+
+    /// the `(` after the function `name`
+    fn open(&mut self, name: &str, span: Span) -> Result<(), Error> {
+        match self.next("`(`")? {
+            (Tok::Symbol("("), _) => Ok(()),
+            (tok, _) => Err(span.error(format!("expected `(` after `{}`, found {}", name, tok))),
+        }
+    }
+
+    /// the `)` that ends the arguments of the function `name`
+    fn close(&mut self, name: &str, span: Span) -> Result<(), Error> {
+        match self.next("`)`")? {
+            (Tok::Symbol(")"), _) => Ok(()),
+            (tok, _) => Err(span.error(format!("`{}(` is never closed, found {}", name, tok))),
+        }
+    }
+
+    fn attribute(&mut self) -> Result<Attribute, Error> {
+        match self.next("an attribute")? {
+            (Tok::Wrapped(kind, inner), span) if kind != "NodeID" && kind != "Ron" => to_attribute(&kind, &inner, span),
+            (tok, span) => Err(span.error(format!("expected `Index(..)`, `Attribute(..)` or `Core(..)`, found {}", tok))),
+        }
+    }
+
+    /// `NodeID(name)`, `NodeID(0x..)` or a bare name or number
+    fn node_id(&mut self) -> Result<NodeID, Error> {
+        match self.next("a NodeID")? {
+            (Tok::Wrapped(kind, inner), span) if kind == "NodeID" => {
+                format!("NodeID({})", inner).parse().map_err(|e: Error| span.error(e.to_string()))
+            },
+            (Tok::Int(i), _) => Ok(NodeID::from_u64(i as u64)),
+            (Tok::Word(name), _) => Ok(NodeID::from_name(&name)),
+            (tok, span) => Err(span.error(format!("expected a NodeID, found {}", tok))),
+        }
+    }
+}
+
+fn to_attribute(kind: &str, inner: &str, span: Span) -> Result<Attribute, Error> {
+    format!("{}({})", kind, inner).parse().map_err(|e: Error| span.error(e.to_string()))
+}
+
+/// parses a whole script, tags first then statements
+pub(super) fn parse(script: &str) -> Result<Script, Error> {
+    let tokens = tokenize(script)?;
+    let lines: Vec<&str> = script.lines().collect();
+    let end = Span {
+        line: lines.len().max(1),
+        column: lines.last().map(|line| line.chars().count() + 1).unwrap_or(1),
+    };
+    Parser { tokens, pos: 0, end }.script()
+}
@@ -0,0 +1,447 @@
+use crate::prelude::{Attribute, NodeID, BevySpriteAnimationError};
+use crate::state::{AnimationState, AttributeValue};
+
+use super::parser::{ArithOp, CmpOp, Expr, Func, Script, Stmt};
+use super::{Value, arithmetic, call, compare, frames_in, negate, set};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use super::super::parser::parse;
+
+    #[test]
+    fn jumps_land_after_blocks() {
+        let program = compile(&parse("if true { set Index(Fall) = 1 } else { return NodeID(0x2) } return NodeID(0x1)").unwrap());
+        assert!(matches!(program.code[..], [
+            Op::Push(Value::Bool(true)), Op::Truth(0), Op::Branch(6),
+            Op::Push(Value::Int(1)), Op::Set(_), Op::Jump(7),
+            Op::Return(_),
+            Op::Return(_),
+        ]));
+    }
+
+    #[test]
+    fn short_circuit_jumps() {
+        let program = compile(&parse("if false and true or true return NodeID(0x1)").unwrap());
+        assert!(matches!(program.code[..], [
+            Op::Push(Value::Bool(false)), Op::Truth(0), Op::JumpIf(false, 5),
+            Op::Push(Value::Bool(true)), Op::Truth(1),
+            Op::Truth(2), Op::JumpIf(true, 9),
+            Op::Push(Value::Bool(true)), Op::Truth(3),
+            Op::Truth(4), Op::Branch(12),
+            Op::Return(_),
+        ]));
+    }
+
+    #[test]
+    fn literals_are_compared_in_place() {
+        let program = compile(&parse("if Index(vm_stand) >= 6 and 2 > Index(vm_stand) return NodeID(0x1)").unwrap());
+        assert!(matches!(program.code[..], [
+            Op::Get(0), Op::CmpWith(CmpOp::Ge, Value::Int(6), false), Op::Truth(0), Op::JumpIf(false, 7),
+            Op::Get(0), Op::CmpWith(CmpOp::Gt, Value::Int(2), true), Op::Truth(1),
+            Op::Truth(2), Op::Branch(10),
+            Op::Return(_),
+        ]));
+        assert_eq!(program.slots.len(), 1);
+    }
+
+    #[test]
+    fn sets_replace_read_values() {
+        let program = compile(&parse("set Index(vm_fall) = 1
+            if Index(vm_fall) == 1 { set Index(vm_fall) = Index(vm_fall) + 1 }
+            if Index(vm_fall) == 2 return NodeID(0x1)").unwrap());
+        let mut state = AnimationState::default();
+        assert_eq!(program.run(&mut state), Ok(Some(NodeID::from_u64(0x1))));
+        assert!(state.script.stack.capacity() > 0);
+        assert_eq!(program.run(&mut state), Ok(Some(NodeID::from_u64(0x1))));
+    }
+
+    #[test]
+    fn attributes_are_resolved() {
+        assert!(matches!(Slot::of(Attribute::DELTA), Slot::Float(_)));
+        assert!(matches!(Slot::of(Attribute::FLIP_X), Slot::Bool(_)));
+        assert!(matches!(Slot::of(Attribute::FRAMES), Slot::Usize(_)));
+        assert!(matches!(Slot::of(Attribute::new_index("Stand")), Slot::Usize(_)));
+        assert!(matches!(Slot::of(Attribute::new_attribute("ZombieState")), Slot::Custom(_)));
+    }
+}
+
+/// where `Op::Get` reads an attribute from, worked out when the script is compiled
+#[derive(Debug, Clone, Copy)]
+pub(super) enum Slot {
+    Float(Attribute),
+    Bool(Attribute),
+    Usize(Attribute),
+    Custom(Attribute),
+}
+
+impl Slot {
+    pub fn of(att: Attribute) -> Slot {
+        if att == Attribute::DELTA || att == Attribute::TIME_ON_FRAME {
+            Slot::Float(att)
+        } else if att == Attribute::FLIP_X || att == Attribute::FLIP_Y {
+            Slot::Bool(att)
+        } else if att.is_index() || att == Attribute::FRAMES {
+            Slot::Usize(att)
+        } else {
+            Slot::Custom(att)
+        }
+    }
+
+    pub fn attribute(self) -> Attribute {
+        match self {
+            Slot::Float(att) | Slot::Bool(att) | Slot::Usize(att) | Slot::Custom(att) => att,
+        }
+    }
+
+    /// the value of the attribute; custom attributes without a type are their raw bytes
+    /// and flips that are not set are false, as they are drawn
+    pub fn get(self, state: &AnimationState) -> Result<Value, String> {
+        let (att, res) = match self {
+            Slot::Float(att) => (att, state.try_get_attribute_or_error::<f32>(&att).map(|v| Value::Float(v as f64))),
+            Slot::Bool(att) => (att, state.try_get_attribute_or_error::<bool>(&att).map(Value::Bool)),
+            Slot::Usize(att) => (att, state.try_get_attribute_or_error::<usize>(&att).map(|v| Value::Int(v as i64))),
+            Slot::Custom(att) => {
+                #[cfg(feature = "ron")]
+                if state.has_data_type(&att) {
+                    return match state.get_attribute_as_ron(&att) {
+                        Ok(data) => Ok(super::from_ron(data)),
+                        Err(BevySpriteAnimationError::AttributeNotFound(_)) => Ok(Value::None),
+                        Err(e) => Err(format!("failed to read {}: {}", att, e)),
+                    };
+                }
+                return match state.try_get_attribute_value(&att).map(AttributeValue::to_bytes) {
+                    Some(Ok(raw)) => Ok(Value::Raw(raw)),
+                    Some(Err(e)) => Err(format!("failed to read {}: {}", att, e)),
+                    None => Ok(Value::None),
+                };
+            },
+        };
+        match res {
+            Ok(value) => Ok(value),
+            Err(BevySpriteAnimationError::AttributeNotFound(_)) if matches!(self, Slot::Bool(_)) => Ok(Value::Bool(false)),
+            Err(BevySpriteAnimationError::AttributeNotFound(_)) => Ok(Value::None),
+            Err(e) => Err(format!("failed to read {}: {}", att, e)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(super) enum Op {
+    Push(Value),
+    /// pushes the attribute in `Program::slots` at this index
+    Get(usize),
+    Neg,
+    Arith(ArithOp),
+    Cmp(CmpOp),
+    /// compares the top of the stack to a literal, `true` when the literal is on the left
+    CmpWith(CmpOp, Value, bool),
+    /// calls the function with this many values from the top of the stack
+    Call(Func, usize),
+    FramesIn(NodeID),
+    /// compares a custom attribute to a `Ron(..)` literal, `true` when the literal is on the left
+    #[cfg(feature = "ron")]
+    CmpRon(Attribute, CmpOp, String, bool),
+    /// errors if the top of the stack is not a bool, using the condition at this index
+    Truth(usize),
+    Not,
+    /// jumps if the top of the stack is this bool, otherwise pops it
+    JumpIf(bool, usize),
+    /// pops the top of the stack and jumps if it is false
+    Branch(usize),
+    Jump(usize),
+    /// pops the top of the stack into the attribute in `Program::slots` at this index
+    Set(usize),
+    Temp(Attribute),
+    Persist(Attribute),
+    Clear(usize),
+    Return(NodeID),
+    /// the name and ron payload of a `ScriptEvent`
+    Emit(String, String),
+}
+
+/// A script compiled to run on a small stack machine
+#[derive(Debug, Clone, Default)]
+pub(super) struct Program {
+    code: Vec<Op>,
+    /// the text of every condition checked by `Op::Truth` for errors
+    conditions: Vec<String>,
+    /// every attribute the script reads or writes, found once when it is compiled
+    slots: Vec<Slot>,
+}
+
+/// the stack and attribute values a `Program` runs with,
+/// kept on each `AnimationState` so running a script does not allocate once it has run
+#[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    stack: Vec<Value>,
+    /// the value of each of `Program::slots` read so far this run; custom attributes are not kept
+    registers: Vec<Option<Value>>,
+}
+
+pub(super) fn compile(script: &Script) -> Program {
+    let mut program = Program::default();
+    program.block(&script.body);
+    program
+}
+
+impl Program {
+    fn block(&mut self, stmts: &[Stmt]) {
+        for stmt in stmts {
+            match stmt {
+                Stmt::If { cond, then, otherwise } => {
+                    self.condition(cond);
+                    let branch = self.emit(Op::Branch(0));
+                    self.block(then);
+                    if otherwise.is_empty() {
+                        self.code[branch] = Op::Branch(self.code.len());
+                    } else {
+                        let jump = self.emit(Op::Jump(0));
+                        self.code[branch] = Op::Branch(self.code.len());
+                        self.block(otherwise);
+                        self.code[jump] = Op::Jump(self.code.len());
+                    }
+                },
+                Stmt::Set(target, value) => {
+                    self.expression(value);
+                    let slot = self.slot(*target);
+                    self.emit(Op::Set(slot));
+                },
+                Stmt::SetTemp(target, value) => {
+                    self.expression(value);
+                    let slot = self.slot(*target);
+                    self.emit(Op::Set(slot));
+                    self.emit(Op::Temp(*target));
+                },
+                Stmt::Persist(target) => {self.emit(Op::Persist(*target));},
+                Stmt::Clear(target) => {
+                    let slot = self.slot(*target);
+                    self.emit(Op::Clear(slot));
+                },
+                Stmt::Return(id) => {
+                    self.emit(Op::Return(*id));
+                },
+                Stmt::Emit(name, payload) => {
+                    self.emit(Op::Emit(name.clone(), payload.clone().unwrap_or_else(|| "()".to_string())));
+                },
+            }
+        }
+    }
+
+    /// an expression that leaves a checked bool on the stack
+    fn condition(&mut self, expr: &Expr) {
+        self.expression(expr);
+        self.conditions.push(expr.to_string());
+        self.emit(Op::Truth(self.conditions.len() - 1));
+    }
+
+    fn expression(&mut self, expr: &Expr) {
+        match expr {
+            Expr::None => {self.emit(Op::Push(Value::None));},
+            Expr::Bool(b) => {self.emit(Op::Push(Value::Bool(*b)));},
+            Expr::Int(i) => {self.emit(Op::Push(Value::Int(*i)));},
+            Expr::Float(v) => {self.emit(Op::Push(Value::Float(v.0)));},
+            Expr::String(s) => {self.emit(Op::Push(Value::String(s.clone())));},
+            Expr::Raw(bytes) => {self.emit(Op::Push(Value::Raw(bytes.clone())));},
+            Expr::Ron(data) => {self.emit(Op::Push(Value::Ron(data.clone())));},
+            Expr::Attribute(att) => {
+                let slot = self.slot(*att);
+                self.emit(Op::Get(slot));
+            },
+            Expr::Neg(expr) => {
+                self.expression(expr);
+                self.emit(Op::Neg);
+            },
+            Expr::Binary(lhs, op, rhs) => {
+                self.expression(lhs);
+                self.expression(rhs);
+                self.emit(Op::Arith(*op));
+            },
+            Expr::Call(func, args) => {
+                for arg in args {
+                    self.expression(arg);
+                }
+                self.emit(Op::Call(*func, args.len()));
+            },
+            Expr::FramesIn(id) => {self.emit(Op::FramesIn(*id));},
+            Expr::Not(expr) => {
+                self.condition(expr);
+                self.emit(Op::Not);
+            },
+            Expr::And(lhs, rhs) => self.short_circuit(lhs, false, rhs),
+            Expr::Or(lhs, rhs) => self.short_circuit(lhs, true, rhs),
+            Expr::Compare(lhs, op, rhs) => {
+                #[cfg(feature = "ron")]
+                match (&**lhs, &**rhs) {
+                    (Expr::Attribute(att), Expr::Ron(data)) if att.is_custom() => {
+                        self.emit(Op::CmpRon(*att, *op, data.clone(), false));
+                        return;
+                    },
+                    (Expr::Ron(data), Expr::Attribute(att)) if att.is_custom() => {
+                        self.emit(Op::CmpRon(*att, *op, data.clone(), true));
+                        return;
+                    },
+                    _ => {},
+                }
+                match (literal(lhs), literal(rhs)) {
+                    (None, Some(value)) => {
+                        self.expression(lhs);
+                        self.emit(Op::CmpWith(*op, value, false));
+                    },
+                    (Some(value), None) => {
+                        self.expression(rhs);
+                        self.emit(Op::CmpWith(*op, value, true));
+                    },
+                    _ => {
+                        self.expression(lhs);
+                        self.expression(rhs);
+                        self.emit(Op::Cmp(*op));
+                    },
+                }
+            },
+        }
+    }
+
+    /// the index of `att` in `slots`, adding it the first time it is used
+    fn slot(&mut self, att: Attribute) -> usize {
+        match self.slots.iter().position(|slot| slot.attribute() == att) {
+            Some(slot) => slot,
+            None => {
+                self.slots.push(Slot::of(att));
+                self.slots.len() - 1
+            },
+        }
+    }
+
+    /// skips `rhs` when `lhs` is `stop`
+    fn short_circuit(&mut self, lhs: &Expr, stop: bool, rhs: &Expr) {
+        self.condition(lhs);
+        let jump = self.emit(Op::JumpIf(stop, 0));
+        self.condition(rhs);
+        self.code[jump] = Op::JumpIf(stop, self.code.len());
+    }
+
+    fn emit(&mut self, op: Op) -> usize {
+        self.code.push(op);
+        self.code.len() - 1
+    }
+
+    /// runs until a `return`; None if the end is reached first
+    pub fn run(&self, state: &mut AnimationState) -> Result<Option<NodeID>, String> {
+        let mut scratch = std::mem::take(&mut state.script);
+        scratch.stack.clear();
+        scratch.registers.clear();
+        scratch.registers.resize(self.slots.len(), None);
+        let res = self.execute(state, &mut scratch);
+        state.script = scratch;
+        res
+    }
+
+    fn execute(&self, state: &mut AnimationState, scratch: &mut Scratch) -> Result<Option<NodeID>, String> {
+        let Scratch { stack, registers } = scratch;
+        let mut pc = 0;
+        while let Some(op) = self.code.get(pc) {
+            pc += 1;
+            match op {
+                Op::Push(value) => stack.push(value.clone()),
+                Op::Get(slot) => {
+                    let value = match (self.slots[*slot], &registers[*slot]) {
+                        (Slot::Custom(_), _) => self.slots[*slot].get(state)?,
+                        (_, Some(value)) => value.clone(),
+                        (read, None) => {
+                            let value = read.get(state)?;
+                            registers[*slot] = Some(value.clone());
+                            value
+                        },
+                    };
+                    stack.push(value);
+                },
+                Op::Neg => {
+                    let value = pop(stack);
+                    stack.push(negate(value)?);
+                },
+                Op::Arith(op) => {
+                    let (rhs, lhs) = (pop(stack), pop(stack));
+                    stack.push(arithmetic(lhs, *op, rhs)?);
+                },
+                Op::Cmp(op) => {
+                    let (rhs, lhs) = (pop(stack), pop(stack));
+                    stack.push(Value::Bool(compare(&lhs, *op, &rhs)?));
+                },
+                Op::CmpWith(op, literal, literal_first) => {
+                    let value = pop(stack);
+                    let res = if *literal_first {compare(literal, *op, &value)?} else {compare(&value, *op, literal)?};
+                    stack.push(Value::Bool(res));
+                },
+                Op::Call(func, count) => {
+                    let args = stack.len().saturating_sub(*count);
+                    let value = call(*func, &stack[args..], state)?;
+                    stack.truncate(args);
+                    stack.push(value);
+                },
+                Op::FramesIn(id) => stack.push(frames_in(state, *id)?),
+                #[cfg(feature = "ron")]
+                Op::CmpRon(att, op, data, ron_first) => {
+                    let res = match super::compare_ron(state, att, *op, data) {
+                        Some(res) => res?,
+                        None => {
+                            let (value, data) = (Slot::Custom(*att).get(state)?, Value::Ron(data.clone()));
+                            if *ron_first {compare(&data, *op, &value)?} else {compare(&value, *op, &data)?}
+                        },
+                    };
+                    stack.push(Value::Bool(res));
+                },
+                Op::Truth(condition) => match stack.last() {
+                    Some(Value::Bool(_)) => {},
+                    value => return Err(format!("`{}` is {:?} not a bool", self.conditions[*condition], value.unwrap_or(&Value::None))),
+                },
+                Op::Not => {
+                    let value = matches!(pop(stack), Value::Bool(false));
+                    stack.push(Value::Bool(value));
+                },
+                Op::JumpIf(stop, to) => if stack.last() == Some(&Value::Bool(*stop)) {
+                    pc = *to;
+                } else {
+                    stack.pop();
+                },
+                Op::Branch(to) => if pop(stack) == Value::Bool(false) {
+                    pc = *to;
+                },
+                Op::Jump(to) => pc = *to,
+                Op::Set(slot) => {
+                    let value = pop(stack);
+                    set(state, self.slots[*slot].attribute(), value)?;
+                    registers[*slot] = None;
+                },
+                Op::Temp(target) => {state.set_temporary(*target);},
+                Op::Persist(target) => {state.set_persistent(target);},
+                Op::Clear(slot) => {
+                    state.clear_attribute(&self.slots[*slot].attribute());
+                    registers[*slot] = None;
+                },
+                Op::Return(id) => return Ok(Some(*id)),
+                Op::Emit(name, payload) => state.emit(name, payload),
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// the value of `expr` if it is a literal
+fn literal(expr: &Expr) -> Option<Value> {
+    match expr {
+        Expr::None => Some(Value::None),
+        Expr::Bool(b) => Some(Value::Bool(*b)),
+        Expr::Int(i) => Some(Value::Int(*i)),
+        Expr::Float(v) => Some(Value::Float(v.0)),
+        Expr::String(s) => Some(Value::String(s.clone())),
+        Expr::Raw(bytes) => Some(Value::Raw(bytes.clone())),
+        Expr::Ron(data) => Some(Value::Ron(data.clone())),
+        _ => None,
+    }
+}
+
+/// the compiler always pushes before an op pops
+fn pop(stack: &mut Vec<Value>) -> Value {
+    stack.pop().unwrap_or(Value::None)
+}
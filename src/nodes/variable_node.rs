@@ -1,7 +1,5 @@
 use crate::node_core::CanLoad;
 use crate::prelude::*;
-use bevy::prelude::Handle;
-use bevy::prelude::Image;
 use bevy::reflect::Reflect;
 use crate::error::BevySpriteAnimationError as Error;
 
@@ -114,14 +112,41 @@ mod test {
         let test_node = test_node.unwrap();
         assert_eq!(test_node.hash(), true_node.hash())
     }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn deserialize_sheet_str() {
+        use crate::prelude::SpriteSheet;
+        let asset_server = test_asset_server();
+        let sheet = SpriteSheet::grid("Zombie1/Zombie1_sheet.png", bevy::prelude::Vec2::splat(64.), 8, 9);
+        let true_node: Box<dyn AnimationNode> = Box::new(VariableNode::from_sheet("Zombie1_Idle", sheet, &[(0, 0.1), (1, 0.2), (2, 0.3)], true, &asset_server));
+        let mut loader = VariableNodeLoader;
+        let test_node = loader.load("
+                VariableNode(
+                name: \"Zombie1_Idle\",
+                sheet: (path: \"Zombie1/Zombie1_sheet.png\", layout: Grid(tile_size: (64., 64.), columns: 8, rows: 9)),
+                frames: [
+                (0, 0.1),
+                (1, 0.2),
+                (2, 0.3),
+                ],
+                ),
+        ", &asset_server).unwrap();
+        assert_eq!(test_node.hash(), true_node.hash());
+    }
 }
 
 #[derive(Debug, Reflect)]
 pub struct VariableNode{
     name: String,
-    frames: Vec<(Handle<Image>, f32)>,
+    frames: Vec<(Frame, f32)>,
     is_loop: bool,
     index: Attribute,
+    /// the sheet the atlas frames were cut from; needed to serialize them
+    #[reflect(ignore)]
+    sheet: Option<SpriteSheet>,
+    /// named markers sent as `AnimationMarker` events when their frame is reached
+    markers: Vec<(usize, String)>,
 }
 
 #[cfg(feature = "bevy-inspector-egui")]
@@ -143,24 +168,49 @@ impl bevy_inspector_egui::Inspectable for IndexNode {
 
 impl VariableNode {
     #[inline(always)]
-    pub fn new(name: &str, frames: &[(Handle<Image>, f32)], is_loop: bool) -> VariableNode {
+    pub fn new<T: Into<Frame> + Clone>(name: &str, frames: &[(T, f32)], is_loop: bool) -> VariableNode {
         VariableNode { 
             name: name.to_string(),
-            frames: frames.to_vec(),
+            frames: frames.iter().map(|(frame, time)| (frame.clone().into(), *time)).collect(),
             is_loop,
             index: Attribute::INDEX,
+            sheet: None,
+            markers: Vec::new(),
         }
     }
 
     #[inline(always)]
-    pub fn new_with_index(name: &str, frames: &[(Handle<Image>, f32)], is_loop: bool, index: Attribute) -> VariableNode {
+    pub fn new_with_index<T: Into<Frame> + Clone>(name: &str, frames: &[(T, f32)], is_loop: bool, index: Attribute) -> VariableNode {
         VariableNode { 
             name: name.to_string(),
-            frames: frames.to_vec(),
+            frames: frames.iter().map(|(frame, time)| (frame.clone().into(), *time)).collect(),
             is_loop,
             index,
+            sheet: None,
+            markers: Vec::new(),
         }
     }
+
+    /// uses the frames at each index in the atlas built from `sheet` for the paired time
+    #[cfg(feature = "serialize")]
+    pub fn from_sheet(name: &str, sheet: SpriteSheet, frames: &[(usize, f32)], is_loop: bool, asset_server: &bevy::prelude::AssetServer) -> VariableNode {
+        let atlas = sheet.load(asset_server);
+        VariableNode {
+            name: name.to_string(),
+            frames: frames.iter().map(|(i, time)| (Frame::Atlas(atlas.clone(), *i), *time)).collect(),
+            is_loop,
+            index: Attribute::INDEX,
+            sheet: Some(sheet),
+            markers: Vec::new(),
+        }
+    }
+
+    /// sends an `AnimationMarker` named `marker` each time `frame` is reached
+    pub fn with_marker(mut self, frame: usize, marker: &str) -> VariableNode {
+        self.markers.push((frame, marker.to_string()));
+        self.markers.sort();
+        self
+    }
 }
 
 #[cfg(feature = "serialize")]
@@ -175,19 +225,32 @@ impl AnimationNode for VariableNode {
         &self.name
     }
 
+    fn sprite_sheet(&self) -> Option<&SpriteSheet> {
+        self.sheet.as_ref()
+    }
+
     fn node_type(&self) -> String {
         "VariableNode".to_string()
     }
 
+    fn frame_count(&self) -> Option<usize> {
+        Some(self.frames.len())
+    }
+
     fn run(&self, state: &mut AnimationState) -> NodeResult {
         assert!(self.frames.len() != 0);
-        let mut index = state.try_get_attribute::<usize>(&self.index).unwrap_or(0);
-        let rem_time = state.get_attribute::<f32>(&Attribute::TIME_ON_FRAME);
-        let frames = state.get_attribute::<usize>(&Attribute::FRAMES);
-        let mut frame_time = state.get_attribute::<f32>(&Attribute::LAST_FPS) * frames as f32 + rem_time;
-        let mut current: &(Handle<Image>, f32) = &self.frames[index % self.frames.len()];
+        let start = state.try_get_attribute::<usize>(&self.index);
+        if start.is_none() {
+            crate::nodes::reach_frame(&self.markers, 0, state);
+        }
+        let mut index = start.unwrap_or(0);
+        let rem_time = state.get(TypedAttribute::TIME_ON_FRAME);
+        let frames = state.get(TypedAttribute::FRAMES);
+        let mut frame_time = state.get(TypedAttribute::LAST_FPS) * frames as f32 + rem_time;
+        let mut current: &(Frame, f32) = &self.frames[index % self.frames.len()];
         while frame_time > current.1 {
             frame_time -= current.1;
+            let last = index;
             index += 1;
             if index >= self.frames.len() {
                 if self.is_loop {
@@ -196,9 +259,12 @@ impl AnimationNode for VariableNode {
                     index = self.frames.len() - 1;
                 }
             }
+            if index != last {
+                crate::nodes::reach_frame(&self.markers, index, state);
+            }
             current = &self.frames[index];
         }
-        state.set_attribute(Attribute::TIME_ON_FRAME, frame_time);
+        state.set(TypedAttribute::TIME_ON_FRAME, frame_time);
         state.set_attribute(self.index, index);
         NodeResult::Done(current.0.clone())
     }
@@ -214,14 +280,16 @@ impl AnimationNode for VariableNode {
         data.push_str("VariableNode(\n\t");
         data.push_str("name: \"");
         data.push_str(&self.name);
-        data.push_str("\",\n\tframes: [\n\t");
+        data.push_str("\",\n\t");
+        if let Some(sheet) = &self.sheet {
+            data.push_str("sheet: ");
+            data.push_str(&ron::to_string(sheet)?);
+            data.push_str(",\n\t");
+        }
+        data.push_str("frames: [\n\t");
         for (frame, time) in self.frames.iter() {
-            data.push_str("(");
-            if let Some(path) = asset_server.get_handle_path(frame) {
-                data.push_str(path.path().to_str().unwrap())
-            } else {
-                return Err(Error::AssetPathNotFound(frame.clone_weak()));
-            }
+            data.push('(');
+            data.push_str(&crate::nodes::serialize_frame(frame, self.sheet.as_ref(), asset_server)?);
             data.push_str(&format!(", {})", time));
             data.push_str(",\n\t");
         }
@@ -229,6 +297,10 @@ impl AnimationNode for VariableNode {
         data.push_str(&format!("is_loop: {},\n\t",self.is_loop));
         data.push_str("index: ");
         data.push_str(&ron::to_string(&self.index)?);
+        if !self.markers.is_empty() {
+            data.push_str(",\n\tmarkers: ");
+            data.push_str(&ron::to_string(&self.markers)?);
+        }
         data.push_str(",\n\t),\n");
         Ok(())
     }
@@ -245,6 +317,7 @@ impl AnimationNode for VariableNode {
         for (frame, _) in self.frames.iter() {
             frame.hash(&mut hasher);
         }
+        self.markers.hash(&mut hasher);
         hasher.finish()
     }
 
@@ -258,8 +331,7 @@ pub use loader::VariableNodeLoader;
 
 #[cfg(feature = "serialize")]
 mod loader {
-use crate::{node_core::NodeLoader, prelude::Attribute};
-use std::collections::HashMap;
+use crate::{node_core::NodeLoader, prelude::{Attribute, SpriteSheet}};
 use super::VariableNode;
 
 use crate::prelude::{AnimationNode, BevySpriteAnimationError as Error};
@@ -269,72 +341,35 @@ impl NodeLoader for VariableNodeLoader {
     fn load(&mut self, data: &str, asset_server: &bevy::prelude::AssetServer) -> Result<Box<dyn AnimationNode>, Error> {
         let data = data.trim();
         let data = if data.starts_with("VariableNode(") {&data[13..]} else {data};
-        let mut chars = data.chars().peekable();
-        let mut map: HashMap<&str, &str> = HashMap::new();
-        let mut start = 0;
-        let mut len = 0;
-        let mut key = "";
-        let mut is_key = true;
-        if let Some(c) = chars.peek() {if *c == '(' {start += 1; chars.next();}}
-        while let Some(c) = chars.next() {
-            match c {
-                ':' => {if is_key {
-                    key = &data[start..start+len].trim();
-                    start += len + 1;
-                    len = 0;
-                    is_key = false;
-                    }
-                },
-                ',' => if !is_key {
-                    map.insert(key, data[start..start+len].trim());
-                    start += len + 1;
-                    len = 0;
-                    is_key = true;
-                    key = "";
-                }
-                '[' => {
-                    while let Some(c) = chars.next() {
-                        len += 1;
-                        if c == ']' {
-                            len += 1;
-                            break;
-                        }
-                    }
-                }
-                _ => {
-                    len += 1;
-                }
-            }
-        }
-        if len > 0 {
-            map.insert(key, data[start..start+len].trim());
-        }
+        let map = crate::nodes::node_fields(data);
+        let sheet = match map.get("sheet") {
+            Some(v) => {
+                let sheet: SpriteSheet = ron::from_str(v)?;
+                Some((sheet.load(asset_server), sheet))
+            },
+            None => None,
+        };
         let mut frames = Vec::new();
-        let mut paths = if let Some(paths) = map.get("frames") {
-            paths[1..paths.len() - 1].split_terminator(',')
-        } else {
+        let Some(paths) = map.get("frames") else {
             return Err(Error::DeserializeError{
                 node_type: "VariableNode",
                 message: "Failed to find frames".to_string(),
                 loc: crate::here!()});
         };
-        while let Some(path) = paths.next() {
-            let path = path.trim();
-            let (path, time) = {
-                println!("{}", path);
-                if path.len() == 0 {continue;}
-                let time = paths.next().ok_or(Error::DeserializeError { node_type: "VariableNode", message: "Frame Time not found".into(), loc: path.to_string() })?.trim();
-                println!("{}", time);
-                let time = time[..time.len()-1].parse::<f32>().or_else(|e| Err(Error::ParseFloatError(e)))?;
-                (&path[1..], time)
-            };
-            frames.push((asset_server.load(path.trim()), time))
+        for (path, time) in crate::nodes::variable_frames(paths)? {
+            frames.push((crate::nodes::load_frame(path, sheet.as_ref().map(|(atlas, _)| atlas), asset_server)?, time))
         }
 
         let index = match map.get("index") {
             Some(v) => {ron::from_str(v)?},
             None => {Attribute::INDEX}
         };
+
+        let mut markers: Vec<(usize, String)> = match map.get("markers") {
+            Some(v) => {ron::from_str(v)?},
+            None => {Vec::new()}
+        };
+        markers.sort();
         
         let is_loop = match map.get("is_loop") {
             Some(v) => {!v.trim().starts_with("f")},
@@ -354,7 +389,9 @@ impl NodeLoader for VariableNodeLoader {
             name,
             frames,
             index,
-            is_loop
+            is_loop,
+            sheet: sheet.map(|(_, sheet)| sheet),
+            markers,
         }))
     }
 
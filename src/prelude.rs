@@ -1,12 +1,25 @@
 pub use super::attributes::Attribute;
+pub use super::attributes::TypedAttribute;
+#[cfg(feature = "serialize")]
+pub use super::defaults::AttributeDefault;
 pub use super::error::BevySpriteAnimationError;
+pub use super::events::AnimationError;
+pub use super::events::AnimationErrorKind;
+pub use super::events::AnimationMarker;
+pub use super::events::ScriptEvent;
+pub use super::names::NameRegistry;
 pub use super::node_core::AnimationNode;
+pub use super::node_core::Frame;
+pub use super::node_core::NodeEdge;
 pub use super::node_core::NodeID;
 pub use super::node_core::NodeLoader;
 pub use super::node_core::NodeResult;
 pub use super::nodes::*;
+pub use super::sprite_sheet::SpriteSheet;
 pub use super::state::AnimationState;
+pub use super::state::AttributeValue;
 pub use super::system_set::AnimationSet;
+pub use super::validate::ValidationReport;
 pub use super::AnimationNodeTree;
 pub use super::SpriteAnimationPlugin;
 pub use super::StartNode;
@@ -0,0 +1,220 @@
+use bevy::asset::HandleId;
+use bevy::prelude::*;
+use bevy::reflect::TypeUuid;
+#[cfg(feature = "serialize")]
+use serde::{Serialize, Deserialize};
+
+#[cfg(test)]
+mod test {
+    use crate::prelude::IndexNode;
+    use crate::test::test_asset_server;
+    use super::*;
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn grid_from_ron() {
+        let sheet: SpriteSheet = ron::from_str("(
+            path: \"Zombie1/Zombie1_sheet.png\",
+            layout: Grid(tile_size: (64., 64.), columns: 8, rows: 9),
+        )").unwrap();
+        let atlas = sheet.atlas(&test_asset_server());
+        assert_eq!(atlas.len(), 72);
+        assert_eq!(atlas.size, Vec2::new(512., 576.));
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn rects_from_ron() {
+        let sheet: SpriteSheet = ron::from_str("(
+            path: \"Zombie1/Zombie1_sheet.png\",
+            layout: Rects(size: (128., 64.), rects: [
+                (min: (0., 0.), max: (64., 64.)),
+                (min: (64., 0.), max: (128., 64.)),
+            ]),
+        )").unwrap();
+        let atlas = sheet.atlas(&test_asset_server());
+        assert_eq!(atlas.len(), 2);
+        assert_eq!(atlas.textures[1], Rect::new(64., 0., 128., 64.));
+    }
+
+    #[test]
+    fn same_sheet_same_handle() {
+        let a = SpriteSheet::grid("Zombie1/Zombie1_sheet.png", Vec2::splat(64.), 8, 9);
+        let b = SpriteSheet::grid("Zombie1/Zombie1_sheet.png", Vec2::splat(64.), 8, 9);
+        let c = SpriteSheet::grid("Zombie1/Zombie1_sheet.png", Vec2::splat(32.), 16, 18);
+        assert_eq!(a.handle_id(), b.handle_id());
+        assert_ne!(a.handle_id(), c.handle_id());
+        let rects = SpriteSheet::rects("Zombie1/Zombie1_sheet.png", Vec2::new(128., 64.), vec![Rect::new(0., 0., 64., 64.)]);
+        assert_eq!(rects.handle_id(), rects.clone().handle_id());
+        assert_ne!(rects.handle_id(), a.handle_id());
+    }
+
+    #[test]
+    #[cfg(feature = "serialize")]
+    fn atlases_are_per_app() {
+        #[derive(Component)]
+        struct SheetFlag;
+        let app = || {
+            let mut app = App::new();
+            app.add_plugins((MinimalPlugins, AssetPlugin::default()));
+            app.add_asset::<Image>();
+            app.add_asset::<TextureAtlas>();
+            app.init_resource::<crate::AnimationNodeTree<SheetFlag>>();
+            app.add_systems(Update, add_pending_atlases::<SheetFlag>);
+            app
+        };
+        let (mut with_node, mut without_node) = (app(), app());
+        let sheet = SpriteSheet::grid("Zombie1/Zombie1_sheet.png", Vec2::splat(64.), 8, 9);
+        let node = IndexNode::from_sheet("atlas_per_app", sheet.clone(), &[0, 1], true, with_node.world.resource::<AssetServer>());
+        with_node.world.resource_mut::<crate::AnimationNodeTree<SheetFlag>>().add_node(Box::new(node));
+        with_node.update();
+        without_node.update();
+        assert_eq!(with_node.world.resource::<Assets<TextureAtlas>>().get(&Handle::weak(sheet.handle_id())).map(|a| a.len()), Some(72));
+        assert!(without_node.world.resource::<Assets<TextureAtlas>>().get(&Handle::weak(sheet.handle_id())).is_none());
+    }
+}
+
+/// A sprite sheet image and how it is cut up into frames
+/// used to build a `TextureAtlas` for nodes loaded from a str
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub struct SpriteSheet {
+    pub path: String,
+    pub layout: SheetLayout,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serialize", derive(Serialize, Deserialize))]
+pub enum SheetLayout {
+    /// a grid of `columns` by `rows` tiles, indexed left to right, top to bottom
+    Grid {
+        tile_size: Vec2,
+        columns: usize,
+        rows: usize,
+        #[cfg_attr(feature = "serialize", serde(default))]
+        padding: Option<Vec2>,
+        #[cfg_attr(feature = "serialize", serde(default))]
+        offset: Option<Vec2>,
+    },
+    /// one frame per rect in a sheet of `size` pixels
+    Rects {
+        size: Vec2,
+        rects: Vec<Rect>,
+    },
+}
+
+impl std::hash::Hash for SpriteSheet {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.path.hash(state);
+        self.layout.hash(state);
+    }
+}
+
+/// floats are hashed by their bits
+impl std::hash::Hash for SheetLayout {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        fn vec2<H: std::hash::Hasher>(v: Vec2, state: &mut H) {
+            state.write_u32(v.x.to_bits());
+            state.write_u32(v.y.to_bits());
+        }
+        match self {
+            SheetLayout::Grid { tile_size, columns, rows, padding, offset } => {
+                state.write_u8(0);
+                vec2(*tile_size, state);
+                state.write_u64(*columns as u64);
+                state.write_u64(*rows as u64);
+                for v in [padding, offset] {
+                    match v {
+                        Some(v) => {
+                            state.write_u8(1);
+                            vec2(*v, state);
+                        },
+                        None => state.write_u8(0),
+                    }
+                }
+            },
+            SheetLayout::Rects { size, rects } => {
+                state.write_u8(1);
+                vec2(*size, state);
+                state.write_u64(rects.len() as u64);
+                for rect in rects.iter() {
+                    vec2(rect.min, state);
+                    vec2(rect.max, state);
+                }
+            },
+        }
+    }
+}
+
+impl SpriteSheet {
+    pub fn grid(path: &str, tile_size: Vec2, columns: usize, rows: usize) -> SpriteSheet {
+        SpriteSheet {
+            path: path.to_string(),
+            layout: SheetLayout::Grid { tile_size, columns, rows, padding: None, offset: None },
+        }
+    }
+
+    pub fn rects(path: &str, size: Vec2, rects: Vec<Rect>) -> SpriteSheet {
+        SpriteSheet {
+            path: path.to_string(),
+            layout: SheetLayout::Rects { size, rects },
+        }
+    }
+
+    /// the id of the `TextureAtlas` for this sheet
+    /// the same sheet always has the same id so it is only built once; stable across Rust versions, see `StableHasher`
+    pub fn handle_id(&self) -> HandleId {
+        use std::hash::Hash;
+        use std::hash::Hasher;
+        let mut hasher = crate::hash::StableHasher::default();
+        self.hash(&mut hasher);
+        HandleId::Id(TextureAtlas::TYPE_UUID, hasher.finish())
+    }
+
+    /// builds the `TextureAtlas` for this sheet loading the sheet image with `asset_server`
+    pub fn atlas(&self, asset_server: &AssetServer) -> TextureAtlas {
+        let texture = asset_server.load(&self.path);
+        match &self.layout {
+            SheetLayout::Grid { tile_size, columns, rows, padding, offset } => {
+                TextureAtlas::from_grid(texture, *tile_size, *columns, *rows, *padding, *offset)
+            },
+            SheetLayout::Rects { size, rects } => {
+                let mut atlas = TextureAtlas::new_empty(texture, *size);
+                for rect in rects.iter() {
+                    atlas.add_texture(*rect);
+                }
+                atlas
+            },
+        }
+    }
+
+    /// Returns a handle to the `TextureAtlas` for this sheet
+    /// the atlas is added to `Assets<TextureAtlas>` by the `SpriteAnimationPlugin`
+    /// before the next animation update after a node using it is inserted into the `AnimationNodeTree`
+    pub fn load(&self, asset_server: &AssetServer) -> Handle<TextureAtlas> {
+        asset_server.get_handle(self.handle_id())
+    }
+}
+
+/// builds the atlases of the sheets used by nodes inserted into the tree since the last update
+#[cfg(feature = "serialize")]
+pub(crate) fn add_pending_atlases<F: Send + Sync + 'static>(
+    mut tree: ResMut<crate::AnimationNodeTree<F>>,
+    asset_server: Res<AssetServer>,
+    atlases: Option<ResMut<Assets<TextureAtlas>>>,
+) {
+    if tree.pending_atlases.is_empty() {
+        return;
+    }
+    let pending = std::mem::take(&mut tree.pending_atlases);
+    let Some(mut atlases) = atlases else {
+        error!("SpriteSheets were loaded without Assets<TextureAtlas>; add the SpritePlugin");
+        return;
+    };
+    for sheet in pending {
+        let id = sheet.handle_id();
+        if atlases.get(&Handle::weak(id)).is_none() {
+            atlases.set_untracked(id, sheet.atlas(&asset_server));
+        }
+    }
+}
@@ -6,14 +6,99 @@ use serde::{Serialize, de::DeserializeOwned};
 use bevy::prelude::*;
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+mod value;
+
+pub use value::{AttributeValue, CustomValue};
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn wrong_type_is_an_error() {
+        let mut state = AnimationState::default();
+        let walk = Attribute::new_index("state_walk");
+        state.set_attribute(walk, 3usize);
+        assert_eq!(state.get_attribute_ref::<usize>(&walk), &3);
+        assert!(matches!(state.try_get_attribute_or_error::<f32>(&walk), Err(Error::AttributeType { .. })));
+        assert_eq!(state.try_get_attribute::<f32>(&walk), None);
+        assert_eq!(state.try_get_attribute_ref::<f32>(&walk), None);
+        assert!(matches!(state.try_get_attribute_or_error::<u64>(&Attribute::new_index("state_missing")), Err(Error::AttributeNotFound(_))));
+        state.set_attribute_raw(walk, &bincode::serialize(&4usize).unwrap()).unwrap();
+        assert_eq!(state.get_attribute::<usize>(&walk), 4);
+        assert!(state.changed(&walk));
+    }
+
+    #[test]
+    fn typed_get_set() {
+        const RUN: TypedAttribute<usize> = TypedAttribute::index("state_run");
+        let mut state = AnimationState::default();
+        assert_eq!(state.try_get(RUN), None);
+        state.set(RUN, 2);
+        state.set(TypedAttribute::FLIP_X, true);
+        assert_eq!(state.get(RUN), 2);
+        assert_eq!(state.get_attribute::<usize>(&Attribute::new_index("state_run")), 2);
+        assert!(state.get(TypedAttribute::FLIP_X));
+    }
+}
 
 #[derive(Component)]
 pub struct AnimationState {
-    data: HashMap<Attribute,Vec<u8>>,
+    data: HashMap<Attribute, AttributeValue>,
     pub(crate) changed: HashSet<Attribute>,
     pub(crate) temp: HashSet<Attribute>,
+    /// markers reached by the node that is running; sent as `AnimationMarker` events
+    pub(crate) markers: Vec<(String, usize)>,
+    /// events emitted by the node that is running; sent as `ScriptEvent` events
+    pub(crate) events: Vec<(String, String)>,
+    /// the frame count of each node in the tree last run with this state
+    pub(crate) frame_counts: Arc<HashMap<NodeID, usize>>,
+    /// the state of the random numbers used by scripts; set from the entity the first time it is run
+    pub(crate) seed: Option<u64>,
+    /// the `AnimationNodeTree::defaults_generation` last applied to this state
+    pub(crate) defaults: u64,
+    /// reused by every `ScriptNode` run with this state
+    pub(crate) script: crate::nodes::script_node::Scratch,
+    #[cfg(feature = "ron")]
+    data_type: HashMap<Attribute, DataType>,
+    /// the types registered on the tree last run with this state, used for attributes that have not been set
     #[cfg(feature = "ron")]
-    data_type: HashMap<Attribute, Box<fn(&mut Self, key: Attribute, val: &str) -> Result<(), Error>>>
+    pub(crate) attribute_types: Arc<HashMap<Attribute, DataType>>,
+}
+
+/// converts an attribute to and from ron for the type it was last set with
+#[cfg(feature = "ron")]
+#[derive(Clone, Copy)]
+pub(crate) struct DataType {
+    from_ron: fn(&mut AnimationState, key: Attribute, val: &str) -> Result<(), Error>,
+    to_ron: fn(&AnimationState, key: &Attribute) -> Result<String, Error>,
+    ron_to_value: fn(val: &str) -> Result<AttributeValue, Error>,
+}
+
+#[cfg(feature = "ron")]
+impl DataType {
+    pub(crate) fn of<D: Serialize + DeserializeOwned + Send + Sync + 'static>() -> DataType {
+        DataType {
+            from_ron: AnimationState::insert_test::<D>,
+            to_ron: |state, key| Ok(ron::to_string(state.try_get_attribute_ref_or_error::<D>(key)?)?),
+            ron_to_value: |val| Ok(AttributeValue::new(ron::from_str::<D>(val)?)),
+        }
+    }
+
+    /// the type core attributes and indexes are set with
+    fn core(key: &Attribute) -> Option<DataType> {
+        if key.is_index() || *key == Attribute::FRAMES {
+            Some(DataType::of::<usize>())
+        } else if *key == Attribute::DELTA || *key == Attribute::TIME_ON_FRAME || *key == Attribute::LAST_FPS {
+            Some(DataType::of::<f32>())
+        } else if *key == Attribute::FLIP_X || *key == Attribute::FLIP_Y {
+            Some(DataType::of::<bool>())
+        } else {
+            None
+        }
+    }
 }
 
 impl std::fmt::Debug for AnimationState {
@@ -22,6 +107,8 @@ impl std::fmt::Debug for AnimationState {
         .field("data", &self.data)
         .field("changed", &self.changed)
         .field("temp", &self.temp)
+        .field("markers", &self.markers)
+        .field("events", &self.events)
         .finish()
     }
 }
@@ -29,82 +116,108 @@ impl std::fmt::Debug for AnimationState {
 impl Default for AnimationState {
     fn default() -> Self {
         let mut data = HashMap::default();
-        data.insert(Attribute::DELTA,  bincode::serialize(&0.0f32).unwrap());
-        data.insert(Attribute::FRAMES, bincode::serialize(&0usize).unwrap());
-        data.insert(Attribute::FLIP_X, bincode::serialize(&false).unwrap());
-        data.insert(Attribute::FLIP_Y, bincode::serialize(&false).unwrap());
+        data.insert(Attribute::DELTA,  AttributeValue::F32(0.0));
+        data.insert(Attribute::FRAMES, AttributeValue::Usize(0));
         #[cfg(not(feature = "ron"))]
-        let s = Self { data, changed: HashSet::new(), temp: HashSet::new()};
+        let s = Self { data, changed: HashSet::new(), temp: HashSet::new(), markers: Vec::new(), events: Vec::new(), frame_counts: Arc::default(), seed: None, defaults: 0, script: Default::default()};
         #[cfg(feature = "ron")]
-        let s = Self { data, changed: HashSet::new(), temp: HashSet::new(), data_type: HashMap::new()};
+        let s = Self { data, changed: HashSet::new(), temp: HashSet::new(), markers: Vec::new(), events: Vec::new(), frame_counts: Arc::default(), seed: None, defaults: 0, script: Default::default(), data_type: HashMap::new(), attribute_types: Arc::default()};
         s
     }
 }
 
 impl AnimationState {
-    /// will return `D` for attribute panics if the attribute is not set
+    /// will return a clone of `D` for attribute panics if the attribute is not set
     /// or `D` is the wrong type
     /// use try_get_attribute() if you are unsure if the attribute exists
     #[inline(always)]
-    pub fn get_attribute<D: DeserializeOwned>(&self, key: &Attribute) -> D {
-        self.try_get_attribute(key).expect(&format!("Attribute {} Exists", key))
+    pub fn get_attribute<D: Clone + 'static>(&self, key: &Attribute) -> D {
+        self.get_attribute_ref::<D>(key).clone()
     }
 
-    /// will return an `option<D>` attribute panics if `D` is the wrong type
+    /// will return an `option<D>`, None if the attribute is not set or `D` is the wrong type
+    /// use try_get_attribute_or_error() to tell them apart
     #[inline(always)]
-    pub fn try_get_attribute<D: DeserializeOwned>(&self, key: &Attribute) -> Option<D> {
-        match self.try_get_attribute_or_error(key) {
-            Ok(res) => Some(res),
-            Err(e) => match e {
-                BevySpriteAnimationError::AttributeNotFound(_) => None,
-                BevySpriteAnimationError::BincodeError(e) => panic!("Attribute could not be deserialised: {}", e),
-                _ => panic!("How did you get this error; please file bug report"),
-            }
-        }
+    pub fn try_get_attribute<D: Clone + 'static>(&self, key: &Attribute) -> Option<D> {
+        self.try_get_attribute_ref(key).cloned()
     }
 
-    pub(crate) fn try_get_attribute_or_error<D: DeserializeOwned>(&self, key: &Attribute) -> Result<D, Error> {
-        match self.data.get(key) {
-            Some(att) => {Ok(bincode::deserialize(att)?)},
-            None => Err(Error::AttributeNotFound(key.clone()))
-        }
+    /// the attribute as `D`; an `AttributeType` error if it was set with a different type
+    pub fn try_get_attribute_or_error<D: Clone + 'static>(&self, key: &Attribute) -> Result<D, Error> {
+        self.try_get_attribute_ref_or_error(key).cloned()
+    }
+
+    /// like `get_attribute` without copying the value
+    #[inline(always)]
+    pub fn get_attribute_ref<D: 'static>(&self, key: &Attribute) -> &D {
+        self.try_get_attribute_ref_or_error(key).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// like `try_get_attribute` without copying the value
+    #[inline(always)]
+    pub fn try_get_attribute_ref<D: 'static>(&self, key: &Attribute) -> Option<&D> {
+        self.try_get_attribute_ref_or_error(key).ok()
+    }
+
+    /// like `try_get_attribute_or_error` without copying the value
+    pub fn try_get_attribute_ref_or_error<D: 'static>(&self, key: &Attribute) -> Result<&D, Error> {
+        let value = self.data.get(key).ok_or(Error::AttributeNotFound(*key))?;
+        value.get_ref().ok_or_else(|| wrong_type::<D>(*key, value))
+    }
+
+    /// the value of `key`, panics if it is not set
+    #[inline(always)]
+    pub fn get<T: Clone + 'static>(&self, key: TypedAttribute<T>) -> T {
+        self.get_attribute_ref::<T>(&key.attribute()).clone()
+    }
+
+    /// the value of `key` if it is set
+    #[inline(always)]
+    pub fn try_get<T: Clone + 'static>(&self, key: TypedAttribute<T>) -> Option<T> {
+        self.try_get_attribute_ref::<T>(&key.attribute()).cloned()
+    }
+
+    #[inline(always)]
+    pub fn set<T: Serialize + DeserializeOwned + Send + Sync + 'static>(&mut self, key: TypedAttribute<T>, val: T) {
+        self.set_attribute(key.attribute(), val);
     }
 
     #[inline]
-    pub fn get_attribute_raw(&self, key: &Attribute) -> &Vec<u8> {
-        self.try_get_attribute_raw(key).expect(&format!("Attribute {} Exists", key))
+    pub fn get_attribute_value(&self, key: &Attribute) -> &AttributeValue {
+        self.try_get_attribute_value(key).unwrap_or_else(|| panic!("Attribute {} Exists", key))
     }
 
     #[inline]
-    pub fn try_get_attribute_raw(&self, key: &Attribute) -> Option<&Vec<u8>> {
+    pub fn try_get_attribute_value(&self, key: &Attribute) -> Option<&AttributeValue> {
         self.data.get(key)
     }
 
-    #[inline]
-    pub fn get_attribute_raw_mut(&mut self, key: &Attribute) -> &mut Vec<u8> {
-        self.try_get_attribute_raw_mut(key).expect(&format!("Attribute {} Exists", key))
+    /// sets the attribute without a type to read ron as, use `set_attribute` when the type is known
+    pub fn set_attribute_value(&mut self, key: Attribute, val: AttributeValue) {
+        self.change(key);
+        self.data.insert(key, val);
     }
 
-    #[inline]
-    pub fn try_get_attribute_raw_mut(&mut self, key: &Attribute) -> Option<&mut Vec<u8>> {
-        self.data.get_mut(key)
+    /// reads `bytes` as bincode of the type the attribute is already set to
+    pub fn set_attribute_raw(&mut self, key: Attribute, bytes: &[u8]) -> Result<(), Error> {
+        self.data.get_mut(&key).ok_or(Error::AttributeNotFound(key))?.set_bytes(bytes)?;
+        self.change(key);
+        Ok(())
     }
 
     #[cfg(feature = "ron")]
-    pub fn set_attribute<D: Serialize + DeserializeOwned>(&mut self, key: Attribute, val: D) {
-        match bincode::serialize(&val) {
-            Ok(v) => {
-                //todo make return something
-                self.change(key);
-                self.data.insert(key, v);
-                self.data_type.insert(key, Box::new(Self::insert_test::<D>));
-            },
-            Err(e) => {error!("Failed to serialize {:?}:{}",key, e);}
+    pub fn set_attribute<D: Serialize + DeserializeOwned + Send + Sync + 'static>(&mut self, key: Attribute, val: D) {
+        let val = AttributeValue::new(val);
+        // the types of core values are known from the value so are only kept for custom attributes
+        if key.is_custom() || matches!(val, AttributeValue::Custom(_)) {
+            self.data_type.insert(key, DataType::of::<D>());
         }
+        self.change(key);
+        self.data.insert(key, val);
     }
 
     #[cfg(feature = "ron")]
-    fn insert_test<D: Serialize + DeserializeOwned>(&mut self, key: Attribute, val: &str) -> Result<(), Error> {
+    fn insert_test<D: Serialize + DeserializeOwned + Send + Sync + 'static>(&mut self, key: Attribute, val: &str) -> Result<(), Error> {
         match ron::from_str::<D>(val) {
             Ok(v) => {self.set_attribute(key, v); Ok(())},
             Err(e) => Err(Error::RonDeError(e)),
@@ -112,28 +225,55 @@ impl AnimationState {
     }
 
     #[cfg(not(feature = "ron"))]
-    pub fn set_attribute<D: Serialize>(&mut self, key: Attribute, val: D) {
-        match bincode::serialize(&val) {
-            Ok(v) => {
-                //todo make return something
-                self.change(key);
-                self.data.insert(key, v);
-            },
-            Err(e) => {error!("Failed to serialize {:?}:{}",key, e);}
-        }
+    pub fn set_attribute<D: Serialize + DeserializeOwned + Send + Sync + 'static>(&mut self, key: Attribute, val: D) {
+        self.change(key);
+        self.data.insert(key, AttributeValue::new(val));
     }
-    
+
+    /// reads `val` as the type the attribute was last set with,
+    /// or the type registered for it with `AnimationNodeTree::register_attribute` once the state has been animated
     #[cfg(feature = "ron")]
     #[inline(always)]
     pub fn set_attribute_from_ron(&mut self, key: Attribute, val: &str) -> Result<(), Error> {
-        let id = if let Some(id) = self.data_type.get(&key) {
-            id.clone()
+        let id = if let Some(id) = self.data_type(&key) {
+            id.from_ron
         } else {
             return Err(Error::NoTypeId(key));
         };
         id(self, key, val)
     }
 
+    /// true if the attribute has been set with, or registered with, a type that can be converted to and from ron
+    #[cfg(feature = "ron")]
+    pub fn has_data_type(&self, key: &Attribute) -> bool {
+        self.data_type(key).is_some()
+    }
+
+    /// the type the attribute was last set with, or the type registered for it on the tree, or the type of the core attribute
+    #[cfg(feature = "ron")]
+    fn data_type(&self, key: &Attribute) -> Option<DataType> {
+        match self.data.get(key) {
+            Some(AttributeValue::Bool(_)) => Some(DataType::of::<bool>()),
+            Some(AttributeValue::Usize(_)) => Some(DataType::of::<usize>()),
+            Some(AttributeValue::F32(_)) => Some(DataType::of::<f32>()),
+            _ => self.data_type.get(key).or_else(|| self.attribute_types.get(key)).copied().or_else(|| DataType::core(key)),
+        }
+    }
+
+    /// the attribute as ron using the type it was set with
+    #[cfg(feature = "ron")]
+    pub fn get_attribute_as_ron(&self, key: &Attribute) -> Result<String, Error> {
+        let id = self.data_type(key).ok_or(Error::NoTypeId(*key))?;
+        (id.to_ron)(self, key)
+    }
+
+    /// the value the attribute would have if it was set to `val`
+    #[cfg(feature = "ron")]
+    pub(crate) fn ron_to_value(&self, key: &Attribute, val: &str) -> Result<AttributeValue, Error> {
+        let id = self.data_type(key).ok_or(Error::NoTypeId(*key))?;
+        (id.ron_to_value)(val)
+    }
+
     pub fn set_persistent(&mut self, temp: &Attribute) -> bool {
         self.temp.remove(temp)
     }
@@ -154,6 +294,43 @@ impl AnimationState {
     pub fn clear_attribute(&mut self, attribute: &Attribute) {
         self.data.remove(attribute);
     }
+
+    /// sends an `AnimationMarker` for the running node once it is done
+    /// used by nodes when they reach a frame with a marker on it
+    pub fn add_marker(&mut self, marker: &str, frame: usize) {
+        self.markers.push((marker.to_string(), frame));
+    }
+
+    /// sends a `ScriptEvent` for the running node once it is done
+    /// `payload` is ron and should be `()` if there is nothing to send
+    pub fn emit(&mut self, name: &str, payload: &str) {
+        self.events.push((name.to_string(), payload.to_string()));
+    }
+
+    /// seeds the random numbers used by scripts, so the same seed picks the same values
+    /// entities are seeded with `Entity::to_bits` the first time they are animated if this is not called
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+    }
+
+    /// the next random number from the seed, using splitmix64
+    pub(crate) fn random(&mut self) -> u64 {
+        let seed = self.seed.get_or_insert(0);
+        *seed = seed.wrapping_add(0x9E3779B97F4A7C15);
+        let mut z = *seed;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+        z ^ (z >> 31)
+    }
+
+    /// the number of frames in `node`, if it is in the tree last run with this state and has frames
+    pub(crate) fn frame_count(&self, node: NodeID) -> Option<usize> {
+        self.frame_counts.get(&node).copied()
+    }
+}
+
+fn wrong_type<D>(attribute: Attribute, value: &AttributeValue) -> Error {
+    Error::AttributeType { attribute, expected: std::any::type_name::<D>(), found: value.type_name() }
 }
 
 pub(crate) fn update_delta<Flag: Component>(
@@ -161,7 +338,7 @@ pub(crate) fn update_delta<Flag: Component>(
     mut states: Query<&mut AnimationState, With<Flag>>,
 ){
     for mut state in states.iter_mut() {
-        state.set_attribute(Attribute::DELTA, time.delta_seconds());
+        state.set(TypedAttribute::DELTA, time.delta_seconds());
     }
 }
 
@@ -192,9 +369,14 @@ pub(crate) fn clear_changed(
 
 pub(crate) fn flip_update(
     mut sprites: Query<(&AnimationState, &mut Sprite)>,
+    mut atlas_sprites: Query<(&AnimationState, &mut TextureAtlasSprite)>,
 ){
     for (state, mut sprite) in sprites.iter_mut() {
-        sprite.flip_x = state.get_attribute(&Attribute::FLIP_X);
-        sprite.flip_y = state.get_attribute(&Attribute::FLIP_Y);
+        sprite.flip_x = state.try_get(TypedAttribute::FLIP_X).unwrap_or(false);
+        sprite.flip_y = state.try_get(TypedAttribute::FLIP_Y).unwrap_or(false);
+    }
+    for (state, mut sprite) in atlas_sprites.iter_mut() {
+        sprite.flip_x = state.try_get(TypedAttribute::FLIP_X).unwrap_or(false);
+        sprite.flip_y = state.try_get(TypedAttribute::FLIP_Y).unwrap_or(false);
     }
 }
\ No newline at end of file
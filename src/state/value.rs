@@ -0,0 +1,163 @@
+use crate::error::BevySpriteAnimationError as Error;
+
+use serde::{Serialize, de::DeserializeOwned};
+
+use std::any::Any;
+use std::sync::Arc;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    enum Mood {
+        Idle,
+        Angry(u8),
+    }
+
+    #[test]
+    fn core_types_are_unboxed() {
+        assert_eq!(AttributeValue::new(3usize), AttributeValue::Usize(3));
+        assert_eq!(AttributeValue::new(0.5f32), AttributeValue::F32(0.5));
+        assert_eq!(AttributeValue::new(true), AttributeValue::Bool(true));
+        assert!(matches!(AttributeValue::new(Mood::Idle), AttributeValue::Custom(_)));
+    }
+
+    #[test]
+    fn reads_are_checked() {
+        let value = AttributeValue::new(3usize);
+        assert_eq!(value.get::<usize>(), Some(3));
+        assert_eq!(value.get_ref::<usize>(), Some(&3));
+        assert_eq!(value.get::<f32>(), None);
+        assert_eq!(value.get::<u64>(), None);
+        let mood = AttributeValue::new(Mood::Angry(2));
+        assert_eq!(mood.get::<Mood>(), Some(Mood::Angry(2)));
+        assert_eq!(mood.get::<usize>(), None);
+        assert_eq!(mood.type_name(), std::any::type_name::<Mood>());
+    }
+
+    #[test]
+    fn bytes_keep_the_type() {
+        let mut mood = AttributeValue::new(Mood::Idle);
+        let angry = AttributeValue::new(Mood::Angry(4));
+        mood.set_bytes(&angry.to_bytes().unwrap()).unwrap();
+        assert_eq!(mood, angry);
+        assert!(mood.set_bytes(&[9, 9, 9, 9]).is_err());
+        let mut index = AttributeValue::new(1usize);
+        index.set_bytes(&bincode::serialize(&5usize).unwrap()).unwrap();
+        assert_eq!(index, AttributeValue::Usize(5));
+    }
+}
+
+/// the value of an attribute on an `AnimationState`;
+/// the types of the core attributes are kept as they are, anything else is a `CustomValue`
+#[derive(Debug, Clone, PartialEq)]
+pub enum AttributeValue {
+    Bool(bool),
+    Usize(usize),
+    F32(f32),
+    Custom(CustomValue),
+}
+
+/// a value of any serializable type, shared between clones
+#[derive(Clone)]
+pub struct CustomValue {
+    value: Shared,
+    kind: CustomKind,
+}
+
+type Shared = Arc<dyn Any + Send + Sync>;
+
+/// how a `CustomValue` is converted to and from bincode for the type it was made from
+#[derive(Clone, Copy)]
+struct CustomKind {
+    name: &'static str,
+    to_bytes: fn(&(dyn Any + Send + Sync)) -> Result<Vec<u8>, Error>,
+    from_bytes: fn(&[u8]) -> Result<Shared, Error>,
+}
+
+impl AttributeValue {
+    pub fn new<D: Serialize + DeserializeOwned + Send + Sync + 'static>(val: D) -> AttributeValue {
+        let mut val = Some(val);
+        let any = &mut val as &mut dyn Any;
+        if let Some(v) = any.downcast_mut::<Option<bool>>() {
+            AttributeValue::Bool(v.take().expect("value to be set"))
+        } else if let Some(v) = any.downcast_mut::<Option<usize>>() {
+            AttributeValue::Usize(v.take().expect("value to be set"))
+        } else if let Some(v) = any.downcast_mut::<Option<f32>>() {
+            AttributeValue::F32(v.take().expect("value to be set"))
+        } else {
+            AttributeValue::Custom(CustomValue {
+                value: Arc::new(val.expect("value to be set")),
+                kind: CustomKind {
+                    name: std::any::type_name::<D>(),
+                    to_bytes: |v| Ok(bincode::serialize(v.downcast_ref::<D>().expect("CustomValue to be its kind"))?),
+                    from_bytes: |bytes| Ok(Arc::new(bincode::deserialize::<D>(bytes)?)),
+                },
+            })
+        }
+    }
+
+    /// a clone of the value as `D`; None if it is a different type
+    pub fn get<D: Clone + 'static>(&self) -> Option<D> {
+        self.get_ref().cloned()
+    }
+
+    /// the value as a `&D`; None if it is a different type
+    pub fn get_ref<D: 'static>(&self) -> Option<&D> {
+        match self {
+            AttributeValue::Bool(v) => (v as &dyn Any).downcast_ref(),
+            AttributeValue::Usize(v) => (v as &dyn Any).downcast_ref(),
+            AttributeValue::F32(v) => (v as &dyn Any).downcast_ref(),
+            AttributeValue::Custom(custom) => custom.value.downcast_ref(),
+        }
+    }
+
+    /// the name of the type the value was made from
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            AttributeValue::Bool(_) => std::any::type_name::<bool>(),
+            AttributeValue::Usize(_) => std::any::type_name::<usize>(),
+            AttributeValue::F32(_) => std::any::type_name::<f32>(),
+            AttributeValue::Custom(custom) => custom.kind.name,
+        }
+    }
+
+    /// the value as bincode
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        Ok(match self {
+            AttributeValue::Bool(v) => bincode::serialize(v)?,
+            AttributeValue::Usize(v) => bincode::serialize(v)?,
+            AttributeValue::F32(v) => bincode::serialize(v)?,
+            AttributeValue::Custom(custom) => (custom.kind.to_bytes)(custom.value.as_ref())?,
+        })
+    }
+
+    /// reads `bytes` as bincode of the type the value already is
+    pub fn set_bytes(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        match self {
+            AttributeValue::Bool(v) => *v = bincode::deserialize(bytes)?,
+            AttributeValue::Usize(v) => *v = bincode::deserialize(bytes)?,
+            AttributeValue::F32(v) => *v = bincode::deserialize(bytes)?,
+            AttributeValue::Custom(custom) => custom.value = (custom.kind.from_bytes)(bytes)?,
+        }
+        Ok(())
+    }
+}
+
+impl PartialEq for CustomValue {
+    /// the same type with the same bincode
+    fn eq(&self, other: &Self) -> bool {
+        (*self.value).type_id() == (*other.value).type_id()
+        && match ((self.kind.to_bytes)(self.value.as_ref()), (other.kind.to_bytes)(other.value.as_ref())) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl std::fmt::Debug for CustomValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.kind.name)
+    }
+}
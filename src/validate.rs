@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::prelude::*;
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn valid_tree() {
+        let mut tree = AnimationNodeTree::<()>::default();
+        let index = tree.add_node(Box::new(IndexNode::new::<Frame>("valid_index", &[], true)));
+        let fps = tree.add_node(Box::new(FPSNode::new("valid_fps", 7, index)));
+        let report = tree.validate([fps]);
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn invalid_tree() {
+        let mut tree = AnimationNodeTree::<()>::default();
+        let missing = NodeID::from_name("invalid_missing");
+        let fps = tree.add_node(Box::new(FPSNode::new("invalid_fps", 7, missing)));
+        let unused = tree.add_node(Box::new(FPSNode::new("invalid_unused", 7, fps)));
+        let a = tree.add_node(Box::new(FPSNode::new("invalid_a", 7, NodeID::from_name("invalid_b"))));
+        let b = tree.add_node(Box::new(FPSNode::new("invalid_b", 7, a)));
+        let report = tree.validate([fps, a, NodeID::from_name("invalid_start")]);
+        assert_eq!(report.dangling, vec![(fps, missing)]);
+        assert_eq!(report.unreachable, vec![unused]);
+        assert_eq!(report.endless_cycles.len(), 1);
+        assert!(report.endless_cycles[0].contains(&a) && report.endless_cycles[0].contains(&b));
+        assert_eq!(report.missing_starts, vec![NodeID::from_name("invalid_start")]);
+    }
+}
+
+/// The problems `AnimationNodeTree::validate` found in a tree
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ValidationReport {
+    /// (node, next) for every node that can go to a node that is not in the tree
+    pub dangling: Vec<(NodeID, NodeID)>,
+    /// nodes that can not be reached from any start node
+    pub unreachable: Vec<NodeID>,
+    /// groups of nodes that can only go to each other and never reach a node that returns `NodeResult::Done`
+    pub endless_cycles: Vec<Vec<NodeID>>,
+    /// start nodes that are not in the tree
+    pub missing_starts: Vec<NodeID>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.dangling.is_empty() && self.unreachable.is_empty() && self.endless_cycles.is_empty() && self.missing_starts.is_empty()
+    }
+}
+
+impl std::fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_ok() {
+            return f.write_str("no problems found");
+        }
+        for (node, next) in self.dangling.iter() {
+            writeln!(f, "{} goes to missing node {}", node.name_or_id(), next.name_or_id())?;
+        }
+        for node in self.unreachable.iter() {
+            writeln!(f, "{} can not be reached from a start node", node.name_or_id())?;
+        }
+        for cycle in self.endless_cycles.iter() {
+            let names: Vec<String> = cycle.iter().map(|id| id.name_or_id()).collect();
+            writeln!(f, "[{}] can never reach a node that is done", names.join(", "))?;
+        }
+        for start in self.missing_starts.iter() {
+            writeln!(f, "start node {} is missing", start.name_or_id())?;
+        }
+        Ok(())
+    }
+}
+
+impl<F> AnimationNodeTree<F> {
+    /// Checks the tree for nodes that go to missing nodes, nodes that can not be reached from `starts`,
+    /// cycles that never end in a `NodeResult::Done` and start nodes that are missing
+    /// uses `AnimationNode::next_nodes` so only nodes that implement it are fully checked
+    pub fn validate(&self, starts: impl IntoIterator<Item = NodeID>) -> ValidationReport {
+        let mut report = ValidationReport::default();
+        let edges: HashMap<NodeID, Vec<NodeID>> = self.nodes.iter().map(|(id, node)| (*id, node.next_nodes())).collect();
+
+        for (id, next) in edges.iter() {
+            for next in next.iter() {
+                if !edges.contains_key(next) {
+                    report.dangling.push((*id, *next));
+                }
+            }
+        }
+
+        let mut reachable = HashSet::new();
+        let mut stack = Vec::new();
+        for start in starts {
+            if edges.contains_key(&start) {
+                stack.push(start);
+            } else if !report.missing_starts.contains(&start) {
+                report.missing_starts.push(start);
+            }
+        }
+        while let Some(id) = stack.pop() {
+            if !reachable.insert(id) {
+                continue;
+            }
+            stack.extend(edges[&id].iter().filter(|next| edges.contains_key(next)));
+        }
+        report.unreachable = edges.keys().filter(|id| !reachable.contains(id)).copied().collect();
+
+        // nodes that are done or can go to a node that is done
+        let mut can_finish: HashSet<NodeID> = edges.iter().filter(|(_, next)| next.is_empty()).map(|(id, _)| *id).collect();
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, next) in edges.iter() {
+                if !can_finish.contains(id) && next.iter().any(|next| can_finish.contains(next)) {
+                    can_finish.insert(*id);
+                    changed = true;
+                }
+            }
+        }
+        for cycle in strongly_connected(&edges) {
+            if cycle.iter().all(|id| !can_finish.contains(id)) {
+                report.endless_cycles.push(cycle);
+            }
+        }
+
+        report.dangling.sort_by_key(|(id, next)| (id.as_u64(), next.as_u64()));
+        report.unreachable.sort_by_key(|id| id.as_u64());
+        report
+    }
+}
+
+/// every group of nodes that can all reach each other, including a node that can go to itself
+fn strongly_connected(edges: &HashMap<NodeID, Vec<NodeID>>) -> Vec<Vec<NodeID>> {
+    struct Tarjan<'a> {
+        edges: &'a HashMap<NodeID, Vec<NodeID>>,
+        index: HashMap<NodeID, usize>,
+        low: HashMap<NodeID, usize>,
+        stack: Vec<NodeID>,
+        on_stack: HashSet<NodeID>,
+        groups: Vec<Vec<NodeID>>,
+    }
+
+    impl Tarjan<'_> {
+        fn visit(&mut self, id: NodeID) {
+            let index = self.index.len();
+            self.index.insert(id, index);
+            self.low.insert(id, index);
+            self.stack.push(id);
+            self.on_stack.insert(id);
+            for next in self.edges[&id].iter() {
+                if !self.edges.contains_key(next) {
+                    continue;
+                }
+                if !self.index.contains_key(next) {
+                    self.visit(*next);
+                    let low = self.low[&id].min(self.low[next]);
+                    self.low.insert(id, low);
+                } else if self.on_stack.contains(next) {
+                    let low = self.low[&id].min(self.index[next]);
+                    self.low.insert(id, low);
+                }
+            }
+            if self.low[&id] == self.index[&id] {
+                let mut group = Vec::new();
+                while let Some(member) = self.stack.pop() {
+                    self.on_stack.remove(&member);
+                    group.push(member);
+                    if member == id {
+                        break;
+                    }
+                }
+                if group.len() > 1 || self.edges[&id].contains(&id) {
+                    self.groups.push(group);
+                }
+            }
+        }
+    }
+
+    let mut tarjan = Tarjan {
+        edges,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        groups: Vec::new(),
+    };
+    let mut ids: Vec<NodeID> = edges.keys().copied().collect();
+    ids.sort_by_key(|id| id.as_u64());
+    for id in ids {
+        if !tarjan.index.contains_key(&id) {
+            tarjan.visit(id);
+        }
+    }
+    tarjan.groups
+}